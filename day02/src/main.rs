@@ -1,4 +1,6 @@
+use patch::PatchSet;
 use std::cmp::Eq;
+use std::env;
 use std::fs::File;
 use std::io::prelude::*;
 
@@ -33,7 +35,7 @@ fn parse_input(s: &str) -> Vec<usize> {
         };
         v.push(n);
     }
-    return v;
+    v
 }
 
 fn parse_instruction(ip: usize, mem: &[usize]) -> Instruction {
@@ -68,7 +70,7 @@ fn run_instr(mem: &mut [usize], op: &Instruction) -> bool {
         Instruction::Add(p1, p2, res) => mem[*res] = p1 + p2,
         Instruction::Mul(p1, p2, res) => mem[*res] = p1 * p2,
     }
-    return true;
+    true
 }
 
 fn run_computer(mem: Vec<usize>) -> Vec<usize> {
@@ -84,7 +86,7 @@ fn run_computer(mem: Vec<usize>) -> Vec<usize> {
         }
         ip += 4;
     }
-    return mem;
+    mem
 }
 
 fn main() {
@@ -102,27 +104,45 @@ fn main() {
     println!("Parsing input...");
     let input = parse_input(&input);
 
+    let patch_path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "alarm.patch".to_string());
+    println!("Reading patch set from {}...", patch_path);
+    let mut patch = String::new();
+    {
+        let mut file = File::open(&patch_path).expect("Could not open patch file.");
+        file.read_to_string(&mut patch)
+            .expect("Could not read patch file.");
+    }
+    let mut patch = PatchSet::<usize>::parse(&patch).expect("Could not parse patch file");
+
     println!("\n--- Part 1: ---\n");
 
     let mut mem1 = input.clone();
-    mem1[1] = 12;
-    mem1[2] = 2;
+    patch.apply(&mut mem1).expect("Could not apply patch set");
 
     let mem1 = run_computer(mem1);
 
-    println!("Value at position 0: {}", mem1[0]);
+    for (name, val) in patch.read(&mem1).expect("Could not read patch set") {
+        println!("{}: {}", name, val);
+    }
 
     println!("\n--- Part 2: ---\n");
 
     for noun in 1..=99 {
         for verb in 1..=99 {
             let mut mem = input.clone();
-            mem[1] = noun;
-            mem[2] = verb;
+            let found = patch.set("noun", noun) && patch.set("verb", verb);
+            assert!(found, "Patch set has no noun or verb cell");
+            patch.apply(&mut mem).expect("Could not apply patch set");
 
             let mem = run_computer(mem);
 
-            if mem[0] == 19690720 {
+            let output = patch
+                .read_one("output", &mem)
+                .expect("Could not read patch set")
+                .expect("Patch set has no output cell");
+            if output == 19690720 {
                 println!("N/V-Combo: {}", 100 * noun + verb);
                 break;
             }
@@ -133,7 +153,7 @@ fn main() {
 #[test]
 fn test_p1t1() {
     let input = "1,9,10,3,2,3,11,0,99,30,40,50";
-    let mem = parse_input(&input);
+    let mem = parse_input(input);
     let mut exp_mem = mem.clone();
     exp_mem[0] = 3500;
     exp_mem[3] = 70;
@@ -146,7 +166,7 @@ fn test_p1t1() {
 #[test]
 fn test_p1t2() {
     let input = "1,0,0,0,99";
-    let mem = parse_input(&input);
+    let mem = parse_input(input);
     let mut exp_mem = mem.clone();
     exp_mem[0] = 2;
 
@@ -158,7 +178,7 @@ fn test_p1t2() {
 #[test]
 fn test_p1t3() {
     let input = "2,3,0,3,99";
-    let mem = parse_input(&input);
+    let mem = parse_input(input);
     let mut exp_mem = mem.clone();
     exp_mem[3] = 6;
 
@@ -170,7 +190,7 @@ fn test_p1t3() {
 #[test]
 fn test_p1t4() {
     let input = "2,4,4,5,99,0";
-    let mem = parse_input(&input);
+    let mem = parse_input(input);
     let mut exp_mem = mem.clone();
     exp_mem[5] = 9801;
 
@@ -182,7 +202,7 @@ fn test_p1t4() {
 #[test]
 fn test_p1t5() {
     let input = "1,1,1,4,99,5,6,0,99";
-    let mem = parse_input(&input);
+    let mem = parse_input(input);
     let mut exp_mem = mem.clone();
     exp_mem[0] = 30;
     exp_mem[4] = 2;
@@ -198,3 +218,32 @@ fn test_p1t5() {
     println!("End: {:?}", final_mem);
     assert_eq!(exp_mem, final_mem);
 }
+
+#[test]
+fn test_patch_set() {
+    let mem = parse_input("1,9,10,3,2,3,11,0,99,30,40,50");
+    let mut patch = PatchSet::parse("# comment\nlhs @9 = 3\n  rhs @10=4\n\nresult @0").unwrap();
+
+    let mut patched = mem.clone();
+    patch.apply(&mut patched).unwrap();
+    let final_mem = run_computer(patched);
+    assert_eq!(patch.read_one("result", &final_mem), Ok(Some((3 + 4) * 50)));
+
+    assert!(patch.set("lhs", 5));
+    let mut patched = mem.clone();
+    patch.apply(&mut patched).unwrap();
+    let final_mem = run_computer(patched);
+    assert_eq!(
+        patch.read(&final_mem).unwrap(),
+        vec![
+            ("lhs".to_string(), 5),
+            ("rhs".to_string(), 4),
+            ("result".to_string(), (5 + 4) * 50)
+        ]
+    );
+
+    let mut bad = PatchSet::parse("noun @12 = 1").unwrap();
+    assert!(bad.apply(&mut mem.clone()).is_err());
+    assert!(bad.set("noun", 2));
+    assert!(bad.read(&mem).is_err());
+}
@@ -54,6 +54,11 @@ fn pixel2utf8(pixels: &[u8]) -> String {
     return s;
 }
 
+fn read_message(pixels: &[u8]) -> Result<String, ocr::Error> {
+    let lit = pixels.iter().map(|p| *p == 255).collect::<Vec<_>>();
+    ocr::read_pixels(&lit, WIDTH as usize)
+}
+
 fn parse_input(s: &str) -> Vec<Layer> {
     let mut v = vec![];
 
@@ -117,4 +122,17 @@ fn main() {
 
     println!("Image written to {}", path.display());
     println!("\nText image representation:\n\n{}", pixel2utf8(&pixels));
+
+    match read_message(&pixels) {
+        Ok(s) => println!("Message: {}", s),
+        Err(e) => println!("Could not read message: {}", e),
+    }
+}
+
+#[test]
+fn test_message() {
+    let layers = parse_input(include_str!("../input"));
+    let pixels = layers2pixels(&layers);
+
+    assert_eq!(read_message(&pixels), Ok("PCULA".to_string()));
 }
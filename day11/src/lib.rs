@@ -115,16 +115,12 @@ pub mod icm {
 
         fn run_instr(&mut self) -> bool {
             let i = self.fetch_instruction();
-            // println!("{:>08}: {:?}", self.ip, i);
             match i {
                 Instr::Unknown => {
                     println!("PANIC: Invalid instruction");
                     return false;
                 }
-                Instr::Halt => {
-                    // println!("HALT");
-                    return false;
-                }
+                Instr::Halt => return false,
                 Instr::Add(p0, p1, p2) => {
                     let p0 = self.fetch_param(p0);
                     let p1 = self.fetch_param(p1);
@@ -141,15 +137,18 @@ pub mod icm {
                 }
                 Instr::Store(p0) => {
                     let p0 = self.fetch_addr(p0);
-                    let input = self.input.recv().expect("Processor recv() error");
-                    self.mem[p0] = input;
-                    self.ip += 2;
+                    if let Ok(input) = self.input.recv() {
+                        self.mem[p0] = input;
+                        self.ip += 2;
+                    } else {
+                        return false;
+                    }
                 }
                 Instr::Show(p0) => {
                     let p0 = self.fetch_param(p0);
-                    self.output
-                        .send(p0)
-                        .expect("Processor output channel send error.");
+                    if self.output.send(p0).is_err() {
+                        return false;
+                    }
                     self.ip += 2;
                 }
                 Instr::JmpT(p0, p1) => {
@@ -287,3 +286,546 @@ pub mod icm {
         }
     } // END IMPL Processor
 }
+
+pub mod hull {
+    use crate::icm::Processor;
+    use grid::{Grid, Tile};
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::thread;
+
+    pub type Point = (i32, i32);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Color {
+        Black,
+        White,
+    }
+
+    impl Color {
+        pub fn from_id(id: i128) -> Option<Color> {
+            match id {
+                0 => Some(Color::Black),
+                1 => Some(Color::White),
+                _ => None,
+            }
+        }
+
+        pub fn id(self) -> i128 {
+            match self {
+                Color::Black => 0,
+                Color::White => 1,
+            }
+        }
+    }
+
+    impl Tile for Color {
+        fn all() -> &'static [Color] {
+            &[Color::Black, Color::White]
+        }
+
+        fn symbol(self) -> char {
+            match self {
+                Color::Black => '.',
+                Color::White => '#',
+            }
+        }
+
+        fn name(self) -> &'static str {
+            match self {
+                Color::Black => "black",
+                Color::White => "white",
+            }
+        }
+
+        fn color(self) -> [u8; 3] {
+            match self {
+                Color::Black => [0, 0, 0],
+                Color::White => [255, 255, 255],
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Turn {
+        Left,
+        Right,
+    }
+
+    impl Turn {
+        pub fn from_id(id: i128) -> Option<Turn> {
+            match id {
+                0 => Some(Turn::Left),
+                1 => Some(Turn::Right),
+                _ => None,
+            }
+        }
+    }
+
+    // A brain which answered with something that is not a move.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Error {
+        InvalidColor(i128),
+        InvalidTurn(i128),
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Error::InvalidColor(id) => write!(f, "brain returned the invalid color {}", id),
+                Error::InvalidTurn(id) => write!(f, "brain returned the invalid turn {}", id),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    /*
+    Decides what the robot does. Gets the color of the panel below the robot and
+    returns the color to paint it and where to turn afterwards, or None to stop
+    the robot.
+     */
+    pub trait Brain {
+        fn think(&mut self, color: Color) -> Result<Option<(Color, Turn)>, Error>;
+    }
+
+    impl<F> Brain for F
+    where
+        F: FnMut(Color) -> Option<(Color, Turn)>,
+    {
+        fn think(&mut self, color: Color) -> Result<Option<(Color, Turn)>, Error> {
+            Ok(self(color))
+        }
+    }
+
+    // Replays a fixed list of moves, ignoring the camera.
+    pub struct ScriptedBrain {
+        moves: Vec<(Color, Turn)>,
+        next: usize,
+    }
+
+    impl ScriptedBrain {
+        pub fn new(moves: Vec<(Color, Turn)>) -> ScriptedBrain {
+            ScriptedBrain { moves, next: 0 }
+        }
+    }
+
+    impl Brain for ScriptedBrain {
+        fn think(&mut self, _color: Color) -> Result<Option<(Color, Turn)>, Error> {
+            let mv = self.moves.get(self.next).copied();
+            self.next += 1;
+            Ok(mv)
+        }
+    }
+
+    /*
+    Runs an Intcode program in its own thread. The robot stops as soon as the
+    program halts, which closes the output channel.
+     */
+    pub struct IntcodeBrain {
+        input: Sender<i128>,
+        output: Receiver<i128>,
+    }
+
+    impl IntcodeBrain {
+        pub fn new(program: &[i128]) -> IntcodeBrain {
+            let (send_cpu, recv_cpu) = channel();
+            let (send_bot, recv_bot) = channel();
+            let mut cpu = Processor::new(0, program.to_vec(), recv_cpu, send_bot);
+            thread::spawn(move || cpu.run());
+            IntcodeBrain {
+                input: send_cpu,
+                output: recv_bot,
+            }
+        }
+    }
+
+    impl Brain for IntcodeBrain {
+        fn think(&mut self, color: Color) -> Result<Option<(Color, Turn)>, Error> {
+            // a halted program does not read its input anymore
+            let _ = self.input.send(color.id());
+            let (color, turn) = match (self.output.recv(), self.output.recv()) {
+                (Ok(color), Ok(turn)) => (color, turn),
+                _ => return Ok(None),
+            };
+            let color = Color::from_id(color).ok_or(Error::InvalidColor(color))?;
+            let turn = Turn::from_id(turn).ok_or(Error::InvalidTurn(turn))?;
+            Ok(Some((color, turn)))
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Panel {
+        pub color: Color,
+        // How often the robot painted this panel.
+        pub paint_count: u32,
+    }
+
+    pub struct HullBot {
+        pos: Point,
+        dir: Point,
+        panels: HashMap<Point, Panel>,
+    }
+
+    impl Default for HullBot {
+        fn default() -> Self {
+            HullBot::new()
+        }
+    }
+
+    impl HullBot {
+        pub fn new() -> HullBot {
+            HullBot {
+                pos: (0, 0),
+                dir: (0, 1),
+                panels: HashMap::new(),
+            }
+        }
+
+        pub fn reset(&mut self) {
+            self.pos = (0, 0);
+            self.dir = (0, 1);
+            self.panels.clear();
+        }
+
+        pub fn position(&self) -> Point {
+            self.pos
+        }
+
+        pub fn direction(&self) -> Point {
+            self.dir
+        }
+
+        // All panels the robot painted or which got a color with set_color.
+        pub fn panels(&self) -> &HashMap<Point, Panel> {
+            &self.panels
+        }
+
+        // Panels which were painted at least once.
+        pub fn painted(&self) -> impl Iterator<Item = (&Point, &Panel)> {
+            self.panels.iter().filter(|(_, p)| p.paint_count > 0)
+        }
+
+        pub fn color_at(&self, p: Point) -> Color {
+            match self.panels.get(&p) {
+                Some(panel) => panel.color,
+                None => Color::Black,
+            }
+        }
+
+        // Sets the color of a panel without counting it as painted.
+        pub fn set_color(&mut self, p: Point, color: Color) {
+            self.panels
+                .entry(p)
+                .or_insert(Panel {
+                    color,
+                    paint_count: 0,
+                })
+                .color = color;
+        }
+
+        // The panel colors with y flipped, so that the grid's rows read top down.
+        pub fn grid(&self) -> Grid<Color> {
+            let mut grid = Grid::new();
+            for ((x, y), panel) in &self.panels {
+                grid.set((*x, -y), panel.color);
+            }
+            grid
+        }
+
+        // Sets the panel colors from a grid saved by `grid`, none counts as painted.
+        pub fn set_grid(&mut self, grid: &Grid<Color>) {
+            for ((x, y), color) in grid.cells() {
+                self.set_color((*x, -y), *color);
+            }
+        }
+
+        /*
+        Lets the brain control the robot until it stops or `max_steps` moves were
+        made. Returns the number of moves, or the error of the brain. The moves
+        before the error stay on the hull.
+         */
+        pub fn run<B: Brain>(
+            &mut self,
+            brain: &mut B,
+            max_steps: Option<usize>,
+        ) -> Result<usize, Error> {
+            let max_steps = max_steps.unwrap_or(usize::MAX);
+            let mut steps = 0;
+            while steps < max_steps {
+                let current = self.color_at(self.pos);
+                let (color, turn) = match brain.think(current)? {
+                    Some(mv) => mv,
+                    None => break,
+                };
+                let panel = self.panels.entry(self.pos).or_insert(Panel {
+                    color,
+                    paint_count: 0,
+                });
+                panel.color = color;
+                panel.paint_count += 1;
+                match turn {
+                    Turn::Left => self.turn_left(),
+                    Turn::Right => self.turn_right(),
+                }
+                self.forward();
+                steps += 1;
+            }
+            Ok(steps)
+        }
+
+        fn turn_left(&mut self) {
+            let (x, y) = self.dir;
+            self.dir = (-y, x);
+        }
+
+        fn turn_right(&mut self) {
+            let (x, y) = self.dir;
+            self.dir = (y, -x);
+        }
+
+        fn forward(&mut self) {
+            self.pos.0 += self.dir.0;
+            self.pos.1 += self.dir.1;
+        }
+    }
+}
+
+pub mod render {
+    use crate::hull::{Color, HullBot};
+    use drawille::Canvas;
+    use image::ColorType;
+    use std::io;
+    use std::path::Path;
+
+    /*
+    The white panels of a hull, cropped to their bounding box. Row 0 is the
+    top row, so the image is the right way up.
+     */
+    pub struct Bitmap {
+        width: usize,
+        height: usize,
+        pixels: Vec<bool>,
+    }
+
+    impl Bitmap {
+        pub fn from_hull(bot: &HullBot) -> Bitmap {
+            let white = bot
+                .panels()
+                .iter()
+                .filter(|(_, p)| p.color == Color::White)
+                .map(|(pos, _)| *pos)
+                .collect::<Vec<_>>();
+            if white.is_empty() {
+                return Bitmap {
+                    width: 0,
+                    height: 0,
+                    pixels: vec![],
+                };
+            }
+
+            let min_x = white.iter().map(|p| p.0).min().unwrap();
+            let max_x = white.iter().map(|p| p.0).max().unwrap();
+            let min_y = white.iter().map(|p| p.1).min().unwrap();
+            let max_y = white.iter().map(|p| p.1).max().unwrap();
+            let width = (max_x - min_x + 1) as usize;
+            let height = (max_y - min_y + 1) as usize;
+
+            let mut pixels = vec![false; width * height];
+            for (x, y) in white {
+                // the robot moves up with growing y
+                let col = (x - min_x) as usize;
+                let row = (max_y - y) as usize;
+                pixels[row * width + col] = true;
+            }
+            Bitmap {
+                width,
+                height,
+                pixels,
+            }
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        pub fn get(&self, x: usize, y: usize) -> bool {
+            x < self.width && y < self.height && self.pixels[y * self.width + x]
+        }
+
+        pub fn to_blocks(&self) -> String {
+            let mut s = String::new();
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    s.push(if self.get(x, y) { '\u{2588}' } else { ' ' });
+                }
+                s.push('\n');
+            }
+            s
+        }
+
+        pub fn to_braille(&self) -> String {
+            let mut canvas = Canvas::new(self.width as u32, self.height as u32);
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if self.get(x, y) {
+                        canvas.set(x as u32, y as u32);
+                    }
+                }
+            }
+            canvas.frame()
+        }
+
+        // Writes a black and white PNG with `scale` x `scale` pixels per panel.
+        pub fn save_png(&self, path: &Path, scale: usize) -> io::Result<()> {
+            let (w, h) = (self.width * scale, self.height * scale);
+            let mut buf = vec![0; w * h];
+            for (i, px) in buf.iter_mut().enumerate() {
+                if self.get(i % w / scale, i / w / scale) {
+                    *px = 255;
+                }
+            }
+            image::save_buffer(path, &buf, w as u32, h as u32, ColorType::Gray(8))
+        }
+
+        // Reads the registration identifier.
+        pub fn read_letters(&self) -> Result<String, ocr::Error> {
+            ocr::read_pixels(&self.pixels, self.width)
+        }
+    }
+}
+
+#[test]
+fn test_scripted_brain() {
+    use hull::{Color::*, HullBot, ScriptedBrain, Turn::*};
+
+    let mut brain = ScriptedBrain::new(vec![
+        (White, Left),
+        (Black, Left),
+        (White, Left),
+        (White, Left),
+        (Black, Right),
+        (White, Left),
+        (White, Left),
+    ]);
+    let mut bot = HullBot::new();
+
+    assert_eq!(bot.run(&mut brain, None), Ok(7));
+    assert_eq!(bot.painted().count(), 6);
+    assert_eq!(bot.position(), (0, 1));
+    assert_eq!(bot.direction(), (-1, 0));
+    assert_eq!(bot.panels()[&(0, 0)].paint_count, 2);
+    assert_eq!(bot.color_at((0, 0)), Black);
+    assert_eq!(bot.color_at((1, 1)), White);
+}
+
+#[test]
+fn test_closure_brain() {
+    use hull::{Color, HullBot, Turn};
+
+    // flip every panel and keep turning right: walks a 2x2 square
+    let mut brain = |c| {
+        let flipped = if c == Color::Black {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Some((flipped, Turn::Right))
+    };
+    let mut bot = HullBot::new();
+    bot.set_color((0, 0), Color::White);
+
+    assert_eq!(bot.run(&mut brain, Some(5)), Ok(5));
+    assert_eq!(bot.painted().count(), 4);
+    assert_eq!(bot.panels()[&(0, 0)].paint_count, 2);
+    assert_eq!(bot.color_at((0, 0)), Color::White);
+    assert_eq!(bot.color_at((1, 0)), Color::White);
+}
+
+#[test]
+fn test_intcode_brain() {
+    use hull::{Color, Error, HullBot, IntcodeBrain};
+
+    // paints white and turns right once, then answers with the given color and turn
+    let program = |color, turn| vec![3, 13, 104, 1, 104, 1, 3, 13, 104, color, 104, turn, 99, 0];
+    let mut bot = HullBot::new();
+    assert_eq!(bot.run(&mut IntcodeBrain::new(&program(0, 0)), None), Ok(2));
+    bot.reset();
+    assert_eq!(
+        bot.run(&mut IntcodeBrain::new(&program(2, 0)), None),
+        Err(Error::InvalidColor(2))
+    );
+    assert_eq!(bot.color_at((0, 0)), Color::White);
+    bot.reset();
+    assert_eq!(
+        bot.run(&mut IntcodeBrain::new(&program(1, -1)), None),
+        Err(Error::InvalidTurn(-1))
+    );
+    assert_eq!(
+        Error::InvalidTurn(-1).to_string(),
+        "brain returned the invalid turn -1"
+    );
+}
+
+#[test]
+fn test_render() {
+    use hull::{Color, HullBot};
+    use render::Bitmap;
+
+    // "HI" painted with y growing upwards, starting at a negative offset
+    let rows = [
+        "#..#..###",
+        "#..#...#.",
+        "####...#.",
+        "#..#...#.",
+        "#..#...#.",
+        "#..#..###",
+    ];
+    let mut bot = HullBot::new();
+    for (r, row) in rows.iter().enumerate() {
+        for (c, px) in row.chars().enumerate() {
+            if px == '#' {
+                bot.set_color((c as i32 - 3, -(r as i32) - 2), Color::White);
+            }
+        }
+    }
+    bot.set_color((20, 20), Color::Black);
+
+    let bitmap = Bitmap::from_hull(&bot);
+    assert_eq!((bitmap.width(), bitmap.height()), (9, 6));
+    assert!(bitmap.get(0, 0) && bitmap.get(6, 5) && !bitmap.get(1, 0));
+    assert_eq!(
+        bitmap.to_blocks().lines().nth(2),
+        Some("\u{2588}\u{2588}\u{2588}\u{2588}   \u{2588} ")
+    );
+    assert_eq!(bitmap.read_letters(), Ok("HI".to_string()));
+
+    bot.set_color((5, -3), Color::White);
+    match Bitmap::from_hull(&bot).read_letters() {
+        Err(ocr::Error::UnknownGlyphs { text, .. }) => assert_eq!(text, "H?"),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn test_grid() {
+    use grid::Grid;
+    use hull::{Color, HullBot};
+
+    let mut bot = HullBot::new();
+    bot.set_color((0, 0), Color::White);
+    bot.set_color((1, 1), Color::White);
+    bot.set_color((1, 0), Color::Black);
+
+    let text = bot.grid().to_text();
+    assert!(text.ends_with("\n #\n#.\n"));
+
+    let mut loaded = HullBot::new();
+    loaded.set_grid(&Grid::from_text(&text).unwrap());
+    assert_eq!(loaded.panels(), bot.panels());
+}
@@ -1,14 +1,9 @@
-use day11::icm::Processor;
-use drawille::Canvas;
-use std::collections::HashMap;
-use std::convert::TryInto;
+use day11::hull::{Color, HullBot, IntcodeBrain};
+use day11::render::Bitmap;
+use std::env;
 use std::fs::File;
 use std::io::prelude::Read;
-use std::sync::mpsc::channel;
-use std::sync::mpsc::{Receiver, Sender};
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
+use std::path::Path;
 
 fn parse_input(s: &str) -> Vec<i128> {
     let mut v = vec![];
@@ -22,94 +17,6 @@ fn parse_input(s: &str) -> Vec<i128> {
     return v;
 }
 
-struct HullBot {
-    x: i32,
-    y: i32,
-    dir: (i32, i32),
-    painted: Vec<(i32, i32)>,
-    panels: HashMap<(i32, i32), i128>,
-    input: Receiver<i128>,
-    output: Sender<i128>,
-}
-
-impl HullBot {
-    fn new(inp: Receiver<i128>, out: Sender<i128>) -> HullBot {
-        HullBot {
-            x: 0,
-            y: 0,
-            dir: (0, 1),
-            painted: vec![],
-            panels: HashMap::new(),
-            input: inp,
-            output: out,
-        }
-    }
-
-    fn reset(&mut self) {
-        self.x = 0;
-        self.y = 0;
-        self.dir = (0, 1);
-        self.painted.clear();
-        self.panels.clear();
-    }
-
-    fn paint_white(&mut self) {
-        self.panels.insert((self.x, self.y), 1);
-    }
-
-    fn run(&mut self) {
-        let timeout = Duration::from_millis(100);
-        loop {
-            if let Some(color) = self.panels.get(&(self.x, self.y)) {
-                self.output.send(*color).expect("Sending to cpu failed.");
-            } else {
-                self.panels.insert((self.x, self.y), 0);
-                self.output.send(0).expect("Sending to cpu failed.");
-            }
-            let paint_color = match self.input.recv_timeout(timeout) {
-                Ok(c) => c,
-                _ => break,
-            };
-            self.panels.insert((self.x, self.y), paint_color);
-            if paint_color == 1 {
-                self.painted.push((self.x, self.y));
-            }
-            let turn_dir = self.input.recv().unwrap();
-            if turn_dir == 0 {
-                self.turn_left();
-            } else {
-                self.turn_right();
-            }
-            self.forward();
-        }
-    }
-
-    fn turn_left(&mut self) {
-        match self.dir {
-            (0, 1) => self.dir = (-1, 0),
-            (-1, 0) => self.dir = (0, -1),
-            (0, -1) => self.dir = (1, 0),
-            (1, 0) => self.dir = (0, 1),
-            _ => panic!("Not a valid direction!"),
-        };
-    }
-
-    fn turn_right(&mut self) {
-        match self.dir {
-            (0, 1) => self.dir = (1, 0),
-            (1, 0) => self.dir = (0, -1),
-            (0, -1) => self.dir = (-1, 0),
-            (-1, 0) => self.dir = (0, 1),
-            _ => panic!("Not a valid direction!"),
-        };
-    }
-
-    fn forward(&mut self) {
-        self.x += self.dir.0;
-        self.y += self.dir.1;
-    }
-}
-
 fn main() {
     println!("--- Day 11: Space Police ---\n");
 
@@ -125,74 +32,55 @@ fn main() {
     println!("Parsing input...");
     let program = parse_input(&input);
 
-    let (send_cpu, recv_cpu) = channel();
-    let (send_bot, recv_bot) = channel();
-
     println!("\n--- Part 1: ---\n");
 
-    let cpu = Arc::new(Mutex::new(Processor::new(
-        0,
-        program.clone(),
-        recv_cpu,
-        send_bot,
-    )));
-    let bot = Arc::new(Mutex::new(HullBot::new(recv_bot, send_cpu)));
+    let mut bot = HullBot::new();
+    bot.run(&mut IntcodeBrain::new(&program), None)
+        .expect("The robot's brain failed.");
 
-    let bot_handle = Arc::clone(&bot);
-    let tbot = thread::spawn(move || {
-        let mut bot = bot_handle.lock().unwrap();
-        bot.run();
-    });
-    let cpu_handle = Arc::clone(&cpu);
-    let tcpu = thread::spawn(move || {
-        let mut cpu = cpu_handle.lock().unwrap();
-        cpu.run();
-    });
+    println!("painted panels: {}", bot.painted().count());
 
-    tcpu.join().expect("CPU join error");
-    tbot.join().expect("BOT join error");
+    println!("\n--- Part 2: ---\n");
 
-    {
-        let bot = bot.lock().unwrap();
-        println!("visited panels: {}", bot.panels.len());
-    }
+    bot.reset();
+    bot.set_color((0, 0), Color::White);
+    bot.run(&mut IntcodeBrain::new(&program), None)
+        .expect("The robot's brain failed.");
 
-    println!("\n--- Part 2: ---\n");
+    let bitmap = Bitmap::from_hull(&bot);
 
-    {
-        let mut bot = bot.lock().unwrap();
-        bot.reset();
-        bot.paint_white();
+    // output format: blocks (default), braille, png, or txt/json to save the panel map
+    match env::args().nth(1).as_deref() {
+        Some("braille") => println!("{}", bitmap.to_braille()),
+        Some("png") => {
+            let path = Path::new("registration.png");
+            bitmap.save_png(path, 10).expect("Could not write image.");
+            println!("Image written to {}", path.display());
+        }
+        Some(ext @ "txt") | Some(ext @ "json") => {
+            let path = format!("panels.{}", ext);
+            bot.grid().save(&path).expect("Could not write panel map.");
+            println!("Panel map written to {}", path);
+        }
+        _ => println!("{}", bitmap.to_blocks()),
     }
 
-    {
-        let mut cpu = cpu.lock().unwrap();
-        cpu.reset();
-        cpu.load_into_memory(&program);
+    match bitmap.read_letters() {
+        Ok(s) => println!("Registration identifier: {}", s),
+        Err(e) => println!("Could not read registration identifier: {}", e),
     }
+}
 
-    let bot_handle = Arc::clone(&bot);
-    let tbot = thread::spawn(move || {
-        let mut bot = bot_handle.lock().unwrap();
-        bot.run();
-    });
-    let cpu_handle = Arc::clone(&cpu);
-    let tcpu = thread::spawn(move || {
-        let mut cpu = cpu_handle.lock().unwrap();
-        cpu.run();
-    });
-
-    tcpu.join().expect("CPU join error");
-    tbot.join().expect("BOT join error");
-
-    {
-        let bot = bot.lock().unwrap();
-        let points = &bot.painted;
-        let mut canvas = Canvas::new(50, 10);
-        points
-            .iter()
-            .for_each(|p| canvas.set(p.0.abs().try_into().unwrap(), p.1.abs().try_into().unwrap()));
-
-        println!("{}", canvas.frame());
-    }
+#[test]
+fn test_registration() {
+    let program = parse_input(include_str!("../input"));
+    let mut bot = HullBot::new();
+    bot.set_color((0, 0), Color::White);
+    bot.run(&mut IntcodeBrain::new(&program), None)
+        .expect("The robot's brain failed.");
+
+    assert_eq!(
+        Bitmap::from_hull(&bot).read_letters(),
+        Ok("FARBCFJK".to_string())
+    );
 }
@@ -0,0 +1,85 @@
+// Simulation speed of the original clone-per-step moons against the `Vec`
+// based moons and the fixed-size system, on the second example system of part 2.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use day12::moon::{step_time, Moon};
+use day12::sim::System;
+
+const STEPS: usize = 1000;
+
+fn moons() -> Vec<Moon> {
+    vec![
+        Moon::new(-8, -10, 0),
+        Moon::new(5, 5, 10),
+        Moon::new(2, -7, 3),
+        Moon::new(9, -8, -3),
+    ]
+}
+
+// The first implementation, kept as the baseline: every step clones all
+// moons and collects the gravity of each pair into new vectors.
+fn step_cloning(n: usize, moons: &mut [Moon]) {
+    let mut cpy = moons.to_vec();
+    for _ in 0..n {
+        for moon in moons.iter_mut() {
+            let tmp = moon.clone();
+            let mut gv = vec![];
+            for other in cpy.iter().filter(|m| **m != tmp) {
+                gv.push(
+                    moon.pos
+                        .iter()
+                        .zip(&other.pos)
+                        .map(|(s, o)| (o - s).signum())
+                        .collect::<Vec<_>>(),
+                );
+            }
+            for gravity in &gv {
+                moon.vel = moon.vel.iter().zip(gravity).map(|(v, g)| v + g).collect();
+            }
+            moon.pos = moon.pos.iter().zip(&moon.vel).map(|(p, v)| p + v).collect();
+        }
+        cpy = moons.to_vec();
+    }
+}
+
+fn bench_steps(c: &mut Criterion) {
+    let mut group = c.benchmark_group("1000 steps");
+
+    group.bench_function("clone per step", |b| {
+        b.iter(|| {
+            let mut moons = moons();
+            step_cloning(STEPS, black_box(&mut moons));
+            moons
+        })
+    });
+
+    group.bench_function("Vec<Moon>", |b| {
+        b.iter(|| {
+            let mut moons = moons();
+            step_time(STEPS, black_box(&mut moons));
+            moons
+        })
+    });
+
+    group.bench_function("System<4, 3>", |b| {
+        b.iter(|| {
+            let mut system = System::<4, 3>::from_moons(&moons()).unwrap();
+            black_box(&mut system).steps(STEPS);
+            system
+        })
+    });
+
+    group.bench_function("Axis<4>", |b| {
+        let system = System::<4, 3>::from_moons(&moons()).unwrap();
+        b.iter(|| {
+            let mut axis = system.axes[0];
+            black_box(&mut axis).steps(STEPS);
+            axis
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_steps);
+criterion_main!(benches);
@@ -0,0 +1,1232 @@
+/*
+Rules for how strongly two bodies pull on each other. Every axis is handled on
+its own, so a rule only sees the distance along one axis.
+ */
+pub mod interaction {
+    /*
+    The pull on a body towards another one `delta` away. A rule has to be odd,
+    pull(-delta) == -pull(delta), so that momentum is conserved.
+     */
+    pub trait Interaction {
+        fn pull(&self, delta: i64) -> i64;
+    }
+
+    impl<F: Fn(i64) -> i64> Interaction for F {
+        fn pull(&self, delta: i64) -> i64 {
+            self(delta)
+        }
+    }
+
+    // The puzzle's rule: one unit towards each other.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SignStep;
+
+    impl Interaction for SignStep {
+        fn pull(&self, delta: i64) -> i64 {
+            delta.signum()
+        }
+    }
+
+    // Pulls with the distance, but never more than max.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Capped {
+        pub max: i64,
+    }
+
+    impl Interaction for Capped {
+        fn pull(&self, delta: i64) -> i64 {
+            delta.max(-self.max).min(self.max)
+        }
+    }
+
+    // Pulls with scale / delta, bodies farther away than scale do not interact.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct DistanceWeighted {
+        pub scale: i64,
+    }
+
+    impl Interaction for DistanceWeighted {
+        fn pull(&self, delta: i64) -> i64 {
+            if delta == 0 {
+                0
+            } else {
+                self.scale / delta
+            }
+        }
+    }
+}
+
+pub mod moon {
+    use crate::interaction::{Interaction, SignStep};
+    use crate::series::axis_name;
+    use num::Integer;
+    use std::fmt;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub struct Moon {
+        pub pos: Vec<i64>,
+        pub vel: Vec<i64>,
+        pub mass: i64,
+    }
+
+    impl Moon {
+        pub fn new(x: i64, y: i64, z: i64) -> Moon {
+            Moon::at(vec![x, y, z])
+        }
+
+        // A moon of unit mass at rest, with as many dimensions as the position has.
+        pub fn at(pos: Vec<i64>) -> Moon {
+            let vel = vec![0; pos.len()];
+            Moon { pos, vel, mass: 1 }
+        }
+
+        pub fn with_mass(mut self, mass: i64) -> Moon {
+            self.mass = mass;
+            self
+        }
+
+        pub fn dimensions(&self) -> usize {
+            self.pos.len()
+        }
+
+        pub fn add_vec(v1: &[i64], v2: &[i64]) -> Vec<i64> {
+            v1.iter().zip(v2).map(|(a, b)| a + b).collect()
+        }
+
+        // Pull of m2 on m1 per axis, before it is scaled by m2's mass.
+        pub fn calc_pull<I: Interaction>(m1: &Moon, m2: &Moon, rule: &I) -> Vec<i64> {
+            m1.pos
+                .iter()
+                .zip(&m2.pos)
+                .map(|(s, o)| rule.pull(o - s))
+                .collect()
+        }
+
+        // Change of m1's velocity caused by m2, m2's changes by the negation.
+        pub fn calc_gravity(m1: &Moon, m2: &Moon) -> Vec<i64> {
+            Moon::calc_pull(m1, m2, &SignStep)
+        }
+
+        pub fn apply_gravity(&mut self, gravity: &[i64]) {
+            for (v, g) in self.vel.iter_mut().zip(gravity) {
+                *v += g;
+            }
+        }
+
+        pub fn apply_velocity(&mut self) {
+            self.pos = Moon::add_vec(&self.pos, &self.vel);
+        }
+
+        pub fn potential_energy(&self) -> i64 {
+            self.pos.iter().map(|x| x.abs()).sum()
+        }
+
+        pub fn kinetic_energy(&self) -> i64 {
+            self.vel.iter().map(|x| x.abs()).sum()
+        }
+
+        pub fn total_energy(&self) -> i64 {
+            self.potential_energy() * self.kinetic_energy()
+        }
+    }
+
+    /*
+    The width of every column of the moons, the pos axes first and the vel axes
+    after them. The puzzle pads each column on its own to its largest number and
+    leaves room for a minus sign.
+     */
+    pub fn column_widths(moons: &[Moon]) -> Vec<usize> {
+        let mut widths: Vec<usize> = vec![];
+        for m in moons {
+            for (c, x) in m.pos.iter().chain(&m.vel).enumerate() {
+                let w = x.unsigned_abs().to_string().len() + 1;
+                match widths.get_mut(c) {
+                    Some(width) => *width = (*width).max(w),
+                    None => widths.push(w),
+                }
+            }
+        }
+        widths
+    }
+
+    fn fmt_vec(f: &mut dyn fmt::Write, v: &[i64], widths: &[usize]) -> fmt::Result {
+        write!(f, "<")?;
+        for (a, x) in v.iter().enumerate() {
+            if a > 0 {
+                write!(f, ", ")?;
+            }
+            let width = widths.get(a).copied().unwrap_or(0);
+            write!(f, "{}={:>w$}", axis_name(a), x, w = width)?;
+        }
+        write!(f, ">")
+    }
+
+    impl Moon {
+        // The state with the numbers of every column right-aligned to its width.
+        pub fn to_padded(&self, widths: &[usize]) -> String {
+            let mut s = String::new();
+            self.write_padded(&mut s, widths)
+                .expect("Writing to a String can not fail.");
+            s
+        }
+
+        fn write_padded(&self, f: &mut dyn fmt::Write, widths: &[usize]) -> fmt::Result {
+            let (pos, vel) = widths.split_at(self.pos.len().min(widths.len()));
+            write!(f, "pos=")?;
+            fmt_vec(f, &self.pos, pos)?;
+            write!(f, ", vel=")?;
+            fmt_vec(f, &self.vel, vel)
+        }
+    }
+
+    // The format the puzzle prints states in, `pos=<x= 1, y=-2, z= 3>, vel=<x= 0, y= 0, z= 0>`.
+    impl fmt::Display for Moon {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.write_padded(f, &column_widths(std::slice::from_ref(self)))
+        }
+    }
+
+    pub fn step_time(n: usize, moons: &mut [Moon]) {
+        step_with(n, moons, &SignStep);
+    }
+
+    /*
+    Simulates n steps under the given rule. The axes do not influence each
+    other, so every axis is simulated on its own for all n steps.
+     */
+    pub fn step_with<I: Interaction>(n: usize, moons: &mut [Moon], rule: &I) {
+        let dims = moons.first().map_or(0, |m| m.dimensions());
+        let mass = moons.iter().map(|m| m.mass).collect::<Vec<_>>();
+        let mut pos = vec![0; moons.len()];
+        let mut vel = vec![0; moons.len()];
+        for axis in 0..dims {
+            for (i, m) in moons.iter().enumerate() {
+                pos[i] = m.pos[axis];
+                vel[i] = m.vel[axis];
+            }
+            for _ in 0..n {
+                step_axis(&mut pos, &mut vel, &mass, rule);
+            }
+            for (i, m) in moons.iter_mut().enumerate() {
+                m.pos[axis] = pos[i];
+                m.vel[axis] = vel[i];
+            }
+        }
+    }
+
+    /*
+    Steps one axis given the positions, velocities and masses of all moons on
+    it. The pull of every pair is calculated once and applied to both in
+    opposite directions, scaled by the mass of the other moon.
+     */
+    pub fn step_axis<I: Interaction>(pos: &mut [i64], vel: &mut [i64], mass: &[i64], rule: &I) {
+        for i in 0..pos.len() {
+            for j in i + 1..pos.len() {
+                let p = rule.pull(pos[j] - pos[i]);
+                vel[i] += p * mass[j];
+                vel[j] -= p * mass[i];
+            }
+        }
+        for (p, v) in pos.iter_mut().zip(vel.iter()) {
+            *p += v;
+        }
+    }
+
+    fn axis_period<I: Interaction>(
+        moons: &[Moon],
+        axis: usize,
+        rule: &I,
+        limit: u64,
+    ) -> Option<u64> {
+        let pos0 = moons.iter().map(|m| m.pos[axis]).collect::<Vec<_>>();
+        let vel0 = moons.iter().map(|m| m.vel[axis]).collect::<Vec<_>>();
+        let mass = moons.iter().map(|m| m.mass).collect::<Vec<_>>();
+        let mut pos = pos0.clone();
+        let mut vel = vel0.clone();
+        for steps in 1..=limit {
+            step_axis(&mut pos, &mut vel, &mass, rule);
+            if pos == pos0 && vel == vel0 {
+                return Some(steps);
+            }
+        }
+        None
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Orbit {
+        // Back in the initial state after this many steps.
+        Periodic(u64),
+        // The center of mass moves, the positions grow without bound.
+        Drifting,
+        // An axis did not repeat within the step limit.
+        Unknown,
+    }
+
+    /*
+    Reports whether the moons only ever visit a finite number of states. The
+    pull only depends on the positions, so a step can be reversed and the
+    first state to repeat is always the initial one. Every axis repeats on its
+    own, the whole system when all of them line up. With a net momentum the
+    system drifts off and never repeats.
+     */
+    pub fn orbit<I: Interaction>(moons: &[Moon], rule: &I, limit: u64) -> Orbit {
+        let dims = moons.first().map_or(0, |m| m.dimensions());
+        let drifting = (0..dims).any(|a| moons.iter().map(|m| m.mass * m.vel[a]).sum::<i64>() != 0);
+        if drifting {
+            return Orbit::Drifting;
+        }
+        let mut period = 1;
+        for axis in 0..dims {
+            match axis_period(moons, axis, rule, limit) {
+                Some(p) => period = p.lcm(&period),
+                None => return Orbit::Unknown,
+            }
+        }
+        Orbit::Periodic(period)
+    }
+
+    // Number of steps until the moons are back in their initial state.
+    pub fn period(moons: &[Moon]) -> u64 {
+        match orbit(moons, &SignStep, u64::MAX) {
+            Orbit::Periodic(p) => p,
+            _ => panic!("The moons never return to their initial state."),
+        }
+    }
+}
+
+/*
+Parser for moon descriptions, one moon per line. Accepts the puzzle input
+`<x=1, y=2, z=3>` as well as printed states with `pos=` and `vel=`, with any
+spacing around the tokens. Blank lines are skipped.
+ */
+pub mod parse {
+    use crate::moon::{column_widths, Moon};
+    use crate::series::axis_name;
+    use std::fmt;
+    use std::iter::Peekable;
+    use std::str::CharIndices;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Error {
+        // Something else than `expected` was found, None at the end of the line.
+        Expected {
+            line: usize,
+            column: usize,
+            expected: String,
+            found: Option<char>,
+        },
+        // The number does not fit an i64.
+        Number {
+            line: usize,
+            column: usize,
+            text: String,
+        },
+        // The axes have to be named x, y, z, a3, a4, ... in order.
+        Axis {
+            line: usize,
+            column: usize,
+            expected: String,
+            found: String,
+        },
+        // All vectors have to have as many dimensions as the first position.
+        Dimensions {
+            line: usize,
+            expected: usize,
+            found: usize,
+        },
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Error::Expected {
+                    line,
+                    column,
+                    expected,
+                    found: Some(c),
+                } => write!(
+                    f,
+                    "line {}, column {}: expected {}, found {:?}",
+                    line, column, expected, c
+                ),
+                Error::Expected {
+                    line,
+                    column,
+                    expected,
+                    found: None,
+                } => write!(
+                    f,
+                    "line {}, column {}: expected {}, found end of line",
+                    line, column, expected
+                ),
+                Error::Number { line, column, text } => {
+                    write!(
+                        f,
+                        "line {}, column {}: invalid number {}",
+                        line, column, text
+                    )
+                }
+                Error::Axis {
+                    line,
+                    column,
+                    expected,
+                    found,
+                } => write!(
+                    f,
+                    "line {}, column {}: expected axis {}, found '{}'",
+                    line, column, expected, found
+                ),
+                Error::Dimensions {
+                    line,
+                    expected,
+                    found,
+                } => write!(
+                    f,
+                    "line {}: expected {} dimensions, found {}",
+                    line, expected, found
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    // Position in a single line, columns are counted in characters from 1.
+    struct Cursor<'a> {
+        chars: Peekable<CharIndices<'a>>,
+        line: usize,
+        column: usize,
+    }
+
+    impl<'a> Cursor<'a> {
+        fn new(s: &'a str, line: usize) -> Cursor<'a> {
+            Cursor {
+                chars: s.char_indices().peekable(),
+                line,
+                column: 1,
+            }
+        }
+
+        fn peek(&mut self) -> Option<char> {
+            self.chars.peek().map(|&(_, c)| c)
+        }
+
+        fn bump(&mut self) -> Option<char> {
+            let c = self.chars.next().map(|(_, c)| c);
+            if c.is_some() {
+                self.column += 1;
+            }
+            c
+        }
+
+        fn skip_whitespace(&mut self) {
+            while self.peek().filter(|c| c.is_whitespace()).is_some() {
+                self.bump();
+            }
+        }
+
+        fn error(&mut self, expected: &str) -> Error {
+            Error::Expected {
+                line: self.line,
+                column: self.column,
+                expected: expected.to_string(),
+                found: self.peek(),
+            }
+        }
+
+        fn expect(&mut self, c: char) -> Result<(), Error> {
+            self.skip_whitespace();
+            if self.peek() == Some(c) {
+                self.bump();
+                Ok(())
+            } else {
+                Err(self.error(&format!("'{}'", c)))
+            }
+        }
+
+        fn word(&mut self) -> String {
+            let mut w = String::new();
+            while let Some(c) = self.peek().filter(|c| c.is_alphanumeric()) {
+                w.push(c);
+                self.bump();
+            }
+            w
+        }
+
+        fn number(&mut self) -> Result<i64, Error> {
+            self.skip_whitespace();
+            let column = self.column;
+            let mut text = String::new();
+            if let Some(c) = self.peek().filter(|&c| c == '-' || c == '+') {
+                text.push(c);
+                self.bump();
+            }
+            while let Some(c) = self.peek().filter(char::is_ascii_digit) {
+                text.push(c);
+                self.bump();
+            }
+            if text.is_empty() || text == "-" || text == "+" {
+                return Err(self.error("a number"));
+            }
+            text.parse().map_err(|_| Error::Number {
+                line: self.line,
+                column,
+                text,
+            })
+        }
+
+        // A vector `<x=1, y=2, z=3>`.
+        fn vector(&mut self) -> Result<Vec<i64>, Error> {
+            self.expect('<')?;
+            let mut v = vec![];
+            loop {
+                self.skip_whitespace();
+                let column = self.column;
+                let name = self.word();
+                let expected = axis_name(v.len());
+                if name != expected {
+                    return Err(Error::Axis {
+                        line: self.line,
+                        column,
+                        expected,
+                        found: name,
+                    });
+                }
+                self.expect('=')?;
+                v.push(self.number()?);
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(',') => {
+                        self.bump();
+                    }
+                    Some('>') => {
+                        self.bump();
+                        return Ok(v);
+                    }
+                    _ => return Err(self.error("',' or '>'")),
+                }
+            }
+        }
+
+        // An optional `name=` in front of a vector.
+        fn label(&mut self, name: &str) -> Result<bool, Error> {
+            self.skip_whitespace();
+            if self.peek() == Some('<') {
+                return Ok(false);
+            }
+            let column = self.column;
+            let word = self.word();
+            if word != name {
+                return Err(Error::Expected {
+                    line: self.line,
+                    column,
+                    expected: format!("'{}=' or '<'", name),
+                    found: word.chars().next().or_else(|| self.peek()),
+                });
+            }
+            self.expect('=')?;
+            Ok(true)
+        }
+
+        fn moon(&mut self) -> Result<Moon, Error> {
+            self.label("pos")?;
+            let pos = self.vector()?;
+            let mut moon = Moon::at(pos);
+            self.skip_whitespace();
+            if self.peek() == Some(',') {
+                self.bump();
+                self.skip_whitespace();
+                let column = self.column;
+                if !self.label("vel")? {
+                    return Err(Error::Expected {
+                        line: self.line,
+                        column,
+                        expected: "'vel='".to_string(),
+                        found: Some('<'),
+                    });
+                }
+                let vel = self.vector()?;
+                if vel.len() != moon.dimensions() {
+                    return Err(Error::Dimensions {
+                        line: self.line,
+                        expected: moon.dimensions(),
+                        found: vel.len(),
+                    });
+                }
+                moon.vel = vel;
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                None => Ok(moon),
+                Some(_) => Err(self.error("end of line")),
+            }
+        }
+    }
+
+    pub fn parse_moons(s: &str) -> Result<Vec<Moon>, Error> {
+        let mut moons: Vec<Moon> = vec![];
+        for (i, line) in s.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let moon = Cursor::new(line, i + 1).moon()?;
+            if let Some(first) = moons.first() {
+                if moon.dimensions() != first.dimensions() {
+                    return Err(Error::Dimensions {
+                        line: i + 1,
+                        expected: first.dimensions(),
+                        found: moon.dimensions(),
+                    });
+                }
+            }
+            moons.push(moon);
+        }
+        Ok(moons)
+    }
+
+    // One moon per line in the format the puzzle prints states in, every column padded on its own.
+    pub fn format_moons(moons: &[Moon]) -> String {
+        let widths = column_widths(moons);
+        moons
+            .iter()
+            .map(|m| format!("{}\n", m.to_padded(&widths)))
+            .collect()
+    }
+}
+
+/*
+Allocation free simulation of the moons. Every axis is stored and simulated on
+its own, the axes do not influence each other.
+ */
+pub mod sim {
+    use crate::interaction::{Interaction, SignStep};
+    use crate::moon::{step_axis, Moon};
+
+    // Positions and velocities of N bodies along one axis.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Axis<const N: usize> {
+        pub pos: [i64; N],
+        pub vel: [i64; N],
+    }
+
+    impl<const N: usize> Axis<N> {
+        pub fn new(pos: [i64; N]) -> Axis<N> {
+            Axis { pos, vel: [0; N] }
+        }
+
+        pub fn step(&mut self) {
+            self.step_with(&SignStep);
+        }
+
+        pub fn steps(&mut self, n: usize) {
+            self.steps_with(n, &SignStep);
+        }
+
+        // One step under the given rule, all bodies have unit mass.
+        pub fn step_with<I: Interaction>(&mut self, rule: &I) {
+            step_axis(&mut self.pos, &mut self.vel, &[1; N], rule);
+        }
+
+        pub fn steps_with<I: Interaction>(&mut self, n: usize, rule: &I) {
+            for _ in 0..n {
+                self.step_with(rule);
+            }
+        }
+    }
+
+    // N bodies in D dimensions.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct System<const N: usize, const D: usize> {
+        pub axes: [Axis<N>; D],
+    }
+
+    impl<const N: usize, const D: usize> System<N, D> {
+        // Bodies at rest at the given positions.
+        pub fn new(positions: [[i64; D]; N]) -> System<N, D> {
+            let mut axes = [Axis::new([0; N]); D];
+            for (i, p) in positions.iter().enumerate() {
+                for (a, axis) in axes.iter_mut().enumerate() {
+                    axis.pos[i] = p[a];
+                }
+            }
+            System { axes }
+        }
+
+        // Returns None unless there are exactly N moons of unit mass with D dimensions.
+        pub fn from_moons(moons: &[Moon]) -> Option<System<N, D>> {
+            if moons.len() != N || moons.iter().any(|m| m.dimensions() != D || m.mass != 1) {
+                return None;
+            }
+            let mut axes = [Axis::new([0; N]); D];
+            for (i, m) in moons.iter().enumerate() {
+                for (a, axis) in axes.iter_mut().enumerate() {
+                    axis.pos[i] = m.pos[a];
+                    axis.vel[i] = m.vel[a];
+                }
+            }
+            Some(System { axes })
+        }
+
+        pub fn to_moons(&self) -> Vec<Moon> {
+            (0..N)
+                .map(|i| Moon {
+                    pos: self.position(i).to_vec(),
+                    vel: self.velocity(i).to_vec(),
+                    mass: 1,
+                })
+                .collect()
+        }
+
+        pub fn position(&self, body: usize) -> [i64; D] {
+            let mut p = [0; D];
+            for (a, axis) in self.axes.iter().enumerate() {
+                p[a] = axis.pos[body];
+            }
+            p
+        }
+
+        pub fn velocity(&self, body: usize) -> [i64; D] {
+            let mut v = [0; D];
+            for (a, axis) in self.axes.iter().enumerate() {
+                v[a] = axis.vel[body];
+            }
+            v
+        }
+
+        pub fn step(&mut self) {
+            for axis in self.axes.iter_mut() {
+                axis.step();
+            }
+        }
+
+        pub fn steps(&mut self, n: usize) {
+            for axis in self.axes.iter_mut() {
+                axis.steps(n);
+            }
+        }
+
+        pub fn steps_with<I: Interaction>(&mut self, n: usize, rule: &I) {
+            for axis in self.axes.iter_mut() {
+                axis.steps_with(n, rule);
+            }
+        }
+
+        pub fn total_energy(&self) -> i64 {
+            (0..N)
+                .map(|i| {
+                    let pot: i64 = self.axes.iter().map(|a| a.pos[i].abs()).sum();
+                    let kin: i64 = self.axes.iter().map(|a| a.vel[i].abs()).sum();
+                    pot * kin
+                })
+                .sum()
+        }
+    }
+}
+
+// Recording of energies and trajectories over time.
+pub mod series {
+    use crate::moon::{step_time, Moon};
+    use image::{ImageBuffer, Rgb, RgbImage};
+    use serde::Serialize;
+    use std::fmt;
+    use std::io;
+    use std::path::Path;
+
+    // Colors of the bodies in plots.
+    const COLORS: [[u8; 3]; 6] = [
+        [230, 60, 50],
+        [50, 150, 230],
+        [60, 180, 75],
+        [240, 170, 30],
+        [150, 80, 200],
+        [120, 120, 120],
+    ];
+
+    #[derive(Debug)]
+    pub enum Error {
+        // A plot needs at least one pixel in each direction.
+        EmptyImage { width: u32, height: u32 },
+        // The axis is not below the number of dimensions of the bodies.
+        Axis { axis: usize, dimensions: usize },
+        Io(io::Error),
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Error::EmptyImage { width, height } => {
+                    write!(f, "a plot of {}x{} pixels is empty", width, height)
+                }
+                Error::Axis { axis, dimensions } => write!(
+                    f,
+                    "axis {} does not exist, the bodies have {} dimensions",
+                    axis, dimensions
+                ),
+                Error::Io(e) => write!(f, "{}", e),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    impl From<io::Error> for Error {
+        fn from(e: io::Error) -> Error {
+            Error::Io(e)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct Body {
+        pub pos: Vec<i64>,
+        pub vel: Vec<i64>,
+        pub potential: i64,
+        pub kinetic: i64,
+        pub total: i64,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct Sample {
+        pub step: usize,
+        pub bodies: Vec<Body>,
+    }
+
+    impl Sample {
+        pub fn new(step: usize, moons: &[Moon]) -> Sample {
+            let bodies = moons
+                .iter()
+                .map(|m| Body {
+                    pos: m.pos.clone(),
+                    vel: m.vel.clone(),
+                    potential: m.potential_energy(),
+                    kinetic: m.kinetic_energy(),
+                    total: m.total_energy(),
+                })
+                .collect();
+            Sample { step, bodies }
+        }
+
+        // Total energy of the system.
+        pub fn total_energy(&self) -> i64 {
+            self.bodies.iter().map(|b| b.total).sum()
+        }
+    }
+
+    // Name of an axis in exports: x, y, z, then a3, a4, ...
+    pub fn axis_name(axis: usize) -> String {
+        match axis {
+            0 => "x".to_string(),
+            1 => "y".to_string(),
+            2 => "z".to_string(),
+            a => format!("a{}", a),
+        }
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Serialize)]
+    pub struct Series {
+        pub samples: Vec<Sample>,
+    }
+
+    impl Series {
+        // Simulates the moons for `steps` steps, recording the initial state and every step.
+        pub fn record(moons: &[Moon], steps: usize) -> Series {
+            let mut moons = moons.to_vec();
+            let mut samples = Vec::with_capacity(steps + 1);
+            samples.push(Sample::new(0, &moons));
+            for step in 1..=steps {
+                step_time(1, &mut moons);
+                samples.push(Sample::new(step, &moons));
+            }
+            Series { samples }
+        }
+
+        fn dimensions(&self) -> usize {
+            self.samples
+                .first()
+                .and_then(|s| s.bodies.first())
+                .map_or(0, |b| b.pos.len())
+        }
+
+        // One row per step and body: step, body, energies, positions and velocities.
+        pub fn to_csv(&self) -> String {
+            let dims = self.dimensions();
+            let mut s = String::from("step,body,potential,kinetic,total");
+            for a in 0..dims {
+                s.push_str(&format!(",pos_{}", axis_name(a)));
+            }
+            for a in 0..dims {
+                s.push_str(&format!(",vel_{}", axis_name(a)));
+            }
+            s.push('\n');
+            for sample in &self.samples {
+                for (i, b) in sample.bodies.iter().enumerate() {
+                    s.push_str(&format!(
+                        "{},{},{},{},{}",
+                        sample.step, i, b.potential, b.kinetic, b.total
+                    ));
+                    for v in b.pos.iter().chain(&b.vel) {
+                        s.push_str(&format!(",{}", v));
+                    }
+                    s.push('\n');
+                }
+            }
+            s
+        }
+
+        pub fn to_json(&self) -> String {
+            serde_json::to_string(self).expect("Could not serialize series.")
+        }
+
+        /*
+        Plots the positions of all bodies on one axis over time, one colored
+        line per body on a white background. Fails for an empty image or an
+        axis the bodies do not have.
+         */
+        pub fn plot(&self, axis: usize, width: u32, height: u32) -> Result<RgbImage, Error> {
+            if width == 0 || height == 0 {
+                return Err(Error::EmptyImage { width, height });
+            }
+            let dimensions = self.dimensions();
+            if axis >= dimensions {
+                return Err(Error::Axis { axis, dimensions });
+            }
+            let mut img = ImageBuffer::from_pixel(width, height, Rgb([255, 255, 255]));
+            let values = || {
+                self.samples
+                    .iter()
+                    .flat_map(|s| s.bodies.iter().map(|b| b.pos[axis]))
+            };
+            let (min, max) = match (values().min(), values().max()) {
+                (Some(min), Some(max)) => (min, max),
+                _ => return Ok(img),
+            };
+            let last = self.samples.len().max(2) - 1;
+            let px = |step: usize| (step as u64 * u64::from(width - 1) / last as u64) as i64;
+            let py = |v: i64| {
+                let range = (max - min).max(1);
+                i64::from(height - 1) - (v - min) * i64::from(height - 1) / range
+            };
+
+            let bodies = self.samples[0].bodies.len();
+            for body in 0..bodies {
+                let color = Rgb(COLORS[body % COLORS.len()]);
+                for w in self.samples.windows(2) {
+                    let from = (px(w[0].step), py(w[0].bodies[body].pos[axis]));
+                    let to = (px(w[1].step), py(w[1].bodies[body].pos[axis]));
+                    draw_line(&mut img, from, to, color);
+                }
+            }
+            Ok(img)
+        }
+
+        // Saves one plot per axis as <prefix>_<axis>.png.
+        pub fn save_plots(&self, prefix: &str, width: u32, height: u32) -> Result<(), Error> {
+            for axis in 0..self.dimensions() {
+                let path = format!("{}_{}.png", prefix, axis_name(axis));
+                self.plot(axis, width, height)?.save(Path::new(&path))?;
+            }
+            Ok(())
+        }
+    }
+
+    fn draw_line(img: &mut RgbImage, from: (i64, i64), to: (i64, i64), color: Rgb<u8>) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let n = dx.abs().max(dy.abs()).max(1);
+        for i in 0..=n {
+            let x = from.0 + dx * i / n;
+            let y = from.1 + dy * i / n;
+            img.put_pixel(x as u32, y as u32, color);
+        }
+    }
+}
+
+#[test]
+fn test_system() {
+    use moon::{step_time, Moon};
+    use sim::System;
+
+    let mut moons = vec![
+        Moon::new(-1, 0, 2),
+        Moon::new(2, -10, -7),
+        Moon::new(4, -8, 8),
+        Moon::new(3, 5, -1),
+    ];
+    let mut system = System::new([[-1, 0, 2], [2, -10, -7], [4, -8, 8], [3, 5, -1]]);
+    assert_eq!(System::<4, 3>::from_moons(&moons), Some(system));
+    assert_eq!(System::<3, 3>::from_moons(&moons), None);
+    assert_eq!(System::<4, 2>::from_moons(&moons), None);
+
+    system.step();
+    assert_eq!(system.position(0), [2, -1, 1]);
+    assert_eq!(system.velocity(0), [3, -1, -1]);
+
+    system.steps(9);
+    step_time(10, &mut moons);
+    assert_eq!(system.to_moons(), moons);
+    assert_eq!(system.total_energy(), 179);
+}
+
+#[test]
+fn test_dimensions() {
+    use moon::{period, step_time, Moon};
+    use sim::System;
+
+    // one and four dimensions, two of the bodies in the same state
+    let mut line = vec![Moon::at(vec![3]), Moon::at(vec![3]), Moon::at(vec![-2])];
+    let mut system = System::<3, 1>::from_moons(&line).unwrap();
+    step_time(1, &mut line);
+    assert_eq!(line[0].vel, vec![-1]);
+    assert_eq!(line[1].vel, vec![-1]);
+    assert_eq!(line[2].vel, vec![2]);
+    system.step();
+    assert_eq!(system.to_moons(), line);
+
+    let mut moons = vec![
+        Moon::at(vec![1, 2, 3, 4]),
+        Moon::at(vec![4, 3, 2, 1]),
+        Moon::at(vec![0, 0, 0, 0]),
+        Moon::at(vec![1, 2, 3, 4]),
+    ];
+    let mut system = System::<4, 4>::from_moons(&moons).unwrap();
+    step_time(25, &mut moons);
+    system.steps(25);
+    assert_eq!(system.to_moons(), moons);
+
+    // momentum is conserved as gravity acts in pairs
+    for a in 0..4 {
+        assert_eq!(moons.iter().map(|m| m.vel[a]).sum::<i64>(), 0);
+    }
+    let p = period(&moons);
+    let mut again = moons.clone();
+    step_time(p as usize, &mut again);
+    assert_eq!(again, moons);
+}
+
+#[test]
+fn test_series() {
+    use moon::Moon;
+    use series::{Error, Series};
+
+    let moons = vec![
+        Moon::new(-1, 0, 2),
+        Moon::new(2, -10, -7),
+        Moon::new(4, -8, 8),
+        Moon::new(3, 5, -1),
+    ];
+    let series = Series::record(&moons, 10);
+    assert_eq!(series.samples.len(), 11);
+    assert_eq!(series.samples[0].total_energy(), 0);
+    assert_eq!(series.samples[10].total_energy(), 179);
+    assert_eq!(series.samples[1].bodies[0].pos, vec![2, -1, 1]);
+    assert_eq!(series.samples[1].bodies[0].potential, 4);
+    assert_eq!(series.samples[1].bodies[0].kinetic, 5);
+
+    let csv = series.to_csv();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("step,body,potential,kinetic,total,pos_x,pos_y,pos_z,vel_x,vel_y,vel_z")
+    );
+    assert_eq!(lines.next(), Some("0,0,3,0,0,-1,0,2,0,0,0"));
+    assert_eq!(csv.lines().count(), 1 + 11 * 4);
+
+    let json = series.to_json();
+    assert!(json.starts_with(r#"{"samples":[{"step":0,"bodies":[{"pos":[-1,0,2],"vel":[0,0,0],"#));
+
+    let img = series.plot(0, 50, 20).unwrap();
+    assert_eq!(img.dimensions(), (50, 20));
+    // x positions start between -1 and 4, the first body at -1 is the lowest line
+    let min_x = series
+        .samples
+        .iter()
+        .flat_map(|s| s.bodies.iter().map(|b| b.pos[0]))
+        .min();
+    assert_eq!(min_x, Some(-1));
+    assert_eq!(img.get_pixel(0, 19).0, [230, 60, 50]);
+
+    // a single pixel still works, nothing at all or a fourth axis does not
+    assert_eq!(series.plot(2, 1, 1).unwrap().dimensions(), (1, 1));
+    match series.plot(0, 0, 20) {
+        Err(Error::EmptyImage {
+            width: 0,
+            height: 20,
+        }) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match series.plot(0, 50, 0) {
+        Err(Error::EmptyImage {
+            width: 50,
+            height: 0,
+        }) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match series.plot(3, 50, 20) {
+        Err(
+            e @ Error::Axis {
+                axis: 3,
+                dimensions: 3,
+            },
+        ) => assert_eq!(
+            e.to_string(),
+            "axis 3 does not exist, the bodies have 3 dimensions"
+        ),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn test_interaction() {
+    use interaction::{Capped, DistanceWeighted, Interaction, SignStep};
+    use moon::{orbit, step_time, step_with, Moon, Orbit};
+    use sim::System;
+
+    let moons = vec![
+        Moon::new(-1, 0, 2),
+        Moon::new(2, -10, -7),
+        Moon::new(4, -8, 8),
+        Moon::new(3, 5, -1),
+    ];
+    let mut expected = moons.clone();
+    step_time(10, &mut expected);
+    let mut m = moons.clone();
+    step_with(10, &mut m, &Capped { max: 1 });
+    assert_eq!(m, expected);
+    let mut m = moons.clone();
+    step_with(10, &mut m, &|d: i64| d.signum());
+    assert_eq!(m, expected);
+    assert_eq!(orbit(&moons, &SignStep, 10000), Orbit::Periodic(2772));
+    // the axes repeat after 18, 28 and 44 steps
+    assert_eq!(orbit(&moons, &SignStep, 30), Orbit::Unknown);
+
+    // the heavy moon is pulled less, momentum stays zero
+    let mut pair = vec![Moon::at(vec![0]), Moon::at(vec![10]).with_mass(3)];
+    step_with(1, &mut pair, &Capped { max: 2 });
+    assert_eq!(pair[0].vel, vec![6]);
+    assert_eq!(pair[1].vel, vec![-2]);
+    let p = match orbit(&pair, &SignStep, 10000) {
+        Orbit::Periodic(p) => p,
+        o => panic!("Expected a period, got {:?}", o),
+    };
+    let mut again = pair.clone();
+    step_with(p as usize, &mut again, &SignStep);
+    assert_eq!(again, pair);
+
+    let mut drift = vec![Moon::at(vec![0]), Moon::at(vec![5])];
+    drift[0].vel[0] = 1;
+    assert_eq!(orbit(&drift, &SignStep, 10000), Orbit::Drifting);
+
+    // too far apart to interact, the moons fly apart for ever
+    let rule = DistanceWeighted { scale: 4 };
+    assert_eq!(rule.pull(2), 2);
+    assert_eq!(rule.pull(-3), -1);
+    assert_eq!(rule.pull(5), 0);
+    let mut apart = vec![Moon::at(vec![0]), Moon::at(vec![10])];
+    apart[0].vel[0] = -1;
+    apart[1].vel[0] = 1;
+    assert_eq!(orbit(&apart, &rule, 10000), Orbit::Unknown);
+    assert_eq!(orbit(&apart[..1], &rule, 10000), Orbit::Drifting);
+
+    // the fixed-size system follows the same rules
+    let rule = Capped { max: 3 };
+    let mut system = System::<4, 3>::from_moons(&moons).unwrap();
+    system.steps_with(10, &rule);
+    let mut m = moons.clone();
+    step_with(10, &mut m, &rule);
+    assert_eq!(system.to_moons(), m);
+    assert_ne!(m, expected);
+}
+
+#[test]
+fn test_parse() {
+    use moon::Moon;
+    use parse::{format_moons, parse_moons, Error};
+
+    let moons = parse_moons("<x=-1, y=0, z=2>\r\n\n  <x=2,y=-10 , z = -7>  \r\n").unwrap();
+    assert_eq!(moons, vec![Moon::new(-1, 0, 2), Moon::new(2, -10, -7)]);
+
+    // the puzzle pads the numbers when printing states
+    let printed = "pos=<x= 2, y=-1, z= 1>, vel=<x= 3, y=-1, z=-1>\n";
+    let moons = parse_moons(printed).unwrap();
+    assert_eq!(moons[0].pos, vec![2, -1, 1]);
+    assert_eq!(moons[0].vel, vec![3, -1, -1]);
+    assert_eq!(format_moons(&moons), printed);
+    assert_eq!(moons[0].to_string(), printed.trim_end());
+
+    // states of the first two examples: example 1 after one step, and both
+    // examples after no step, where the columns get different widths
+    for printed in &[
+        "pos=<x= 2, y=-1, z= 1>, vel=<x= 3, y=-1, z=-1>\n\
+         pos=<x= 3, y=-7, z=-4>, vel=<x= 1, y= 3, z= 3>\n\
+         pos=<x= 1, y=-7, z= 5>, vel=<x=-3, y= 1, z=-3>\n\
+         pos=<x= 2, y= 2, z= 0>, vel=<x=-1, y=-3, z= 1>\n",
+        "pos=<x=-1, y=  0, z= 2>, vel=<x= 0, y= 0, z= 0>\n\
+         pos=<x= 2, y=-10, z=-7>, vel=<x= 0, y= 0, z= 0>\n\
+         pos=<x= 4, y= -8, z= 8>, vel=<x= 0, y= 0, z= 0>\n\
+         pos=<x= 3, y=  5, z=-1>, vel=<x= 0, y= 0, z= 0>\n",
+        "pos=<x=-8, y=-10, z=  0>, vel=<x= 0, y= 0, z= 0>\n\
+         pos=<x= 5, y=  5, z= 10>, vel=<x= 0, y= 0, z= 0>\n\
+         pos=<x= 2, y= -7, z=  3>, vel=<x= 0, y= 0, z= 0>\n\
+         pos=<x= 9, y= -8, z= -3>, vel=<x= 0, y= 0, z= 0>\n",
+    ] {
+        let moons = parse_moons(printed).unwrap();
+        assert_eq!(moons.len(), 4);
+        assert_eq!(&format_moons(&moons), printed);
+    }
+
+    let four = vec![Moon::at(vec![1, 2, 3, 4])];
+    assert_eq!(
+        format_moons(&four),
+        "pos=<x= 1, y= 2, z= 3, a3= 4>, vel=<x= 0, y= 0, z= 0, a3= 0>\n"
+    );
+    assert_eq!(parse_moons(&format_moons(&four)).unwrap(), four);
+
+    assert_eq!(
+        parse_moons("<x=1, y=2, z=3>\n<x=1, y=2 z=3>"),
+        Err(Error::Expected {
+            line: 2,
+            column: 11,
+            expected: "',' or '>'".to_string(),
+            found: Some('z'),
+        })
+    );
+    assert_eq!(
+        parse_moons("<x=1, z=2>"),
+        Err(Error::Axis {
+            line: 1,
+            column: 7,
+            expected: "y".to_string(),
+            found: "z".to_string(),
+        })
+    );
+    assert_eq!(
+        parse_moons("<x=99999999999999999999>"),
+        Err(Error::Number {
+            line: 1,
+            column: 4,
+            text: "99999999999999999999".to_string(),
+        })
+    );
+    assert_eq!(
+        parse_moons("pos=<x=1, y=2>, vel=<x=1>"),
+        Err(Error::Dimensions {
+            line: 1,
+            expected: 2,
+            found: 1,
+        })
+    );
+    assert_eq!(
+        parse_moons("<x=1>\n<x=1, y=2>"),
+        Err(Error::Dimensions {
+            line: 2,
+            expected: 1,
+            found: 2,
+        })
+    );
+    assert_eq!(
+        parse_moons("<x=1> 2").unwrap_err().to_string(),
+        "line 1, column 7: expected end of line, found '2'"
+    );
+    assert_eq!(
+        parse_moons("<x=").unwrap_err().to_string(),
+        "line 1, column 4: expected a number, found end of line"
+    );
+}
@@ -1,11 +1,42 @@
 pub mod icm {
-    use std::convert::TryInto;
-    use std::sync::mpsc::{Receiver, Sender};
+    use std::collections::VecDeque;
+    use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+
+    // Default upper bound for addresses a program may access. Keeps a broken
+    // program from growing the memory until the allocation fails.
+    pub const MEMORY_LIMIT: usize = 1 << 24;
+
+    // The reasons a processor stops running.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Stop {
+        // Opcode 99 was executed.
+        Halt,
+        // The opcode at `ip` is not known.
+        InvalidOpcode { ip: usize, opcode: i32 },
+        // A parameter mode of the instruction at `ip` is not known or can not
+        // be used for the parameter (immediate mode for a write address).
+        InvalidMode { ip: usize, opcode: i32 },
+        // The instruction at `ip` accessed or jumped to a negative address or
+        // an address beyond the memory limit.
+        InvalidAddress { ip: usize, addr: i64 },
+        // An arithmetic operation of the instruction at `ip` overflowed.
+        Overflow { ip: usize },
+        // Input was requested, but the input channel is closed.
+        InputClosed,
+        // Input was requested, but none is available and the processor is set
+        // to not wait for it. Running again retries the input instruction.
+        InputRequired,
+        // The output channel is closed.
+        OutputClosed,
+        // Only reported by the arcade: the program drew a tile with an
+        // unknown id at (x, y).
+        InvalidTile { x: i32, y: i32, id: i32 },
+    }
 
     #[derive(Debug)]
     enum Param {
         Immediate(i32),
-        Position(usize),
+        Position(i32),
         Relative(i32),
     }
 
@@ -15,21 +46,17 @@ pub mod icm {
     pcode - Paramter mode code. A number where each digit specifies a parameter mode.
     val   - Value of the parameter.
      */
-    fn parse_param(nth: u32, pcode: i32, val: i32) -> Param {
+    fn parse_param(nth: u32, pcode: i32, val: i32) -> Option<Param> {
         match (pcode / 10_i32.pow(nth)) % 10 {
-            0 => {
-                let p: usize = val.try_into().unwrap();
-                Param::Position(p)
-            }
-            1 => Param::Immediate(val),
-            2 => Param::Relative(val),
-            _ => panic!("Invalide pcode."),
+            0 => Some(Param::Position(val)),
+            1 => Some(Param::Immediate(val)),
+            2 => Some(Param::Relative(val)),
+            _ => None,
         }
     }
 
     #[derive(Debug)]
     enum Instr {
-        Unknown,
         Halt,
         Add(Param, Param, Param),
         Mul(Param, Param, Param),
@@ -42,12 +69,44 @@ pub mod icm {
         RBase(Param),
     }
 
+    // Undo record of a single executed instruction. Holds the state from
+    // right before the instruction ran, so it can be restored by step_back().
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Step {
+        pub ip: usize,
+        pub opcode: i32,
+        pub rel_base: i32,
+        pub mem_len: usize,
+        // (address, previous value) of the memory cell the instruction wrote
+        pub write: Option<(usize, i32)>,
+        pub input: Option<i32>,
+        pub output: Option<i32>,
+    }
+
+    impl Step {
+        fn new(ip: usize, opcode: i32, rel_base: i32, mem_len: usize) -> Step {
+            Step {
+                ip,
+                opcode,
+                rel_base,
+                mem_len,
+                write: None,
+                input: None,
+                output: None,
+            }
+        }
+    }
+
     pub struct Processor {
         ip: usize,
         rel_base: i32,
         mem: Vec<i32>,
         input: Receiver<i32>,
         output: Sender<i32>,
+        history: Option<Vec<Step>>,
+        replay: VecDeque<i32>,
+        mem_limit: usize,
+        wait_for_input: bool,
     }
 
     impl Processor {
@@ -63,12 +122,52 @@ pub mod icm {
                 mem,
                 input,
                 output,
+                history: None,
+                replay: VecDeque::new(),
+                mem_limit: MEMORY_LIMIT,
+                wait_for_input: true,
             }
         }
 
         pub fn reset(&mut self) {
             self.ip = 0;
             self.rel_base = 0;
+            self.replay.clear();
+            if let Some(h) = &mut self.history {
+                h.clear();
+            }
+        }
+
+        pub fn ip(&self) -> usize {
+            self.ip
+        }
+
+        pub fn rel_base(&self) -> i32 {
+            self.rel_base
+        }
+
+        pub fn get_address(&self, ind: usize) -> i32 {
+            self.mem.get(ind).copied().unwrap_or(0)
+        }
+
+        // Starts recording an undo log of every executed instruction.
+        // Memory changes done from outside (set_address, load_into_memory, ...)
+        // are not recorded.
+        pub fn enable_history(&mut self) {
+            if self.history.is_none() {
+                self.history = Some(vec![]);
+            }
+        }
+
+        pub fn disable_history(&mut self) {
+            self.history = None;
+        }
+
+        pub fn history(&self) -> &[Step] {
+            match &self.history {
+                Some(h) => h,
+                None => &[],
+            }
         }
 
         pub fn load_into_memory(&mut self, mem: &[i32]) {
@@ -77,6 +176,9 @@ pub mod icm {
             }
             self.mem.clear();
             self.mem.extend_from_slice(mem);
+            if let Some(h) = &mut self.history {
+                h.clear();
+            }
         }
 
         pub fn set_ip(&mut self, ip: usize) {
@@ -85,6 +187,14 @@ pub mod icm {
 
         pub fn set_memory(&mut self, mem: Vec<i32>) {
             self.mem = mem;
+            if let Some(h) = &mut self.history {
+                h.clear();
+            }
+        }
+
+        // Sets the highest address (exclusive) a program may access.
+        pub fn set_memory_limit(&mut self, limit: usize) {
+            self.mem_limit = limit;
         }
 
         pub fn set_address(&mut self, ind: usize, val: i32) {
@@ -92,12 +202,18 @@ pub mod icm {
             self.mem[ind] = val;
         }
 
+        // If disabled, the processor stops with Stop::InputRequired instead of
+        // blocking when no input is available.
+        pub fn set_wait_for_input(&mut self, wait: bool) {
+            self.wait_for_input = wait;
+        }
+
         pub fn set_input(&mut self, input: Receiver<i32>) {
             self.input = input;
         }
 
         pub fn get_input(&self) -> &Receiver<i32> {
-            return &self.input;
+            &self.input
         }
 
         pub fn set_output(&mut self, output: Sender<i32>) {
@@ -106,189 +222,1160 @@ pub mod icm {
 
         fn safecheck_memory(&mut self, ind: usize) {
             if ind >= self.mem.len() {
-                self.mem.resize(ind * 2, 0);
+                let len = (ind * 2 + 1).min(self.mem_limit.max(ind + 1));
+                self.mem.resize(len, 0);
             }
         }
 
-        pub fn run(&mut self) {
+        // Runs until the processor stops and returns the reason.
+        pub fn run(&mut self) -> Stop {
             loop {
-                if !self.run_instr() {
-                    break;
+                if let Err(stop) = self.run_instr() {
+                    return stop;
                 }
             }
         }
 
-        fn run_instr(&mut self) -> bool {
-            let i = self.fetch_instruction();
+        // Executes a single instruction. Returns the reason if the processor stopped.
+        pub fn step(&mut self) -> Result<(), Stop> {
+            self.run_instr()
+        }
+
+        // Undoes the last recorded instruction: restores ip, rel_base and the
+        // written memory cell, and puts consumed input back in front of the
+        // input queue. Sent output can not be taken back.
+        // Returns the undone step, or None if there is nothing to undo.
+        pub fn step_back(&mut self) -> Option<Step> {
+            let step = self.history.as_mut()?.pop()?;
+            if let Some((addr, val)) = step.write {
+                self.mem[addr] = val;
+            }
+            self.mem.truncate(step.mem_len);
+            if let Some(val) = step.input {
+                self.replay.push_front(val);
+            }
+            self.ip = step.ip;
+            self.rel_base = step.rel_base;
+            Some(step)
+        }
+
+        // Steps back until the instruction which last wrote to `addr` is undone.
+        // The processor is left right before that instruction.
+        pub fn rewind_to_write(&mut self, addr: usize) -> Option<Step> {
+            while let Some(step) = self.step_back() {
+                if let Some((a, _)) = step.write {
+                    if a == addr {
+                        return Some(step);
+                    }
+                }
+            }
+            None
+        }
+
+        // Steps back until the last output instruction is undone.
+        pub fn rewind_to_output(&mut self) -> Option<Step> {
+            while let Some(step) = self.step_back() {
+                if step.output.is_some() {
+                    return Some(step);
+                }
+            }
+            None
+        }
+
+        fn read_input(&mut self) -> Result<i32, Stop> {
+            if let Some(val) = self.replay.pop_front() {
+                return Ok(val);
+            }
+            if self.wait_for_input {
+                return self.input.recv().map_err(|_| Stop::InputClosed);
+            }
+            self.input.try_recv().map_err(|e| match e {
+                TryRecvError::Empty => Stop::InputRequired,
+                TryRecvError::Disconnected => Stop::InputClosed,
+            })
+        }
+
+        fn write(&mut self, step: &mut Option<Step>, addr: usize, val: i32) {
+            if let Some(step) = step {
+                step.write = Some((addr, self.mem[addr]));
+            }
+            self.mem[addr] = val;
+        }
+
+        fn run_instr(&mut self) -> Result<(), Stop> {
+            if self.history.is_none() {
+                return self.exec_instr(&mut None);
+            }
+            let ip = self.ip;
+            let mem_len = self.mem.len();
+            let mut step = Some(Step::new(ip, self.get_address(ip), self.rel_base, mem_len));
+            if let Err(stop) = self.exec_instr(&mut step) {
+                // The memory may have grown for a write address before the
+                // instruction stopped. Nothing is recorded, so shrink it back.
+                self.mem.truncate(mem_len);
+                return Err(stop);
+            }
+            if let (Some(h), Some(step)) = (&mut self.history, step) {
+                h.push(step);
+            }
+            Ok(())
+        }
+
+        // Executes the instruction at ip. Fills in the step if there is one.
+        fn exec_instr(&mut self, step: &mut Option<Step>) -> Result<(), Stop> {
+            let ip = self.ip;
+            let i = self.fetch_instruction()?;
             // println!("{:>08}: {:?}", self.ip, i);
             match i {
-                Instr::Unknown => {
-                    println!("PANIC: Invalid instruction");
-                    return false;
-                }
                 Instr::Halt => {
                     // println!("HALT");
-                    return false;
+                    return Err(Stop::Halt);
                 }
                 Instr::Add(p0, p1, p2) => {
-                    let p0 = self.fetch_param(p0);
-                    let p1 = self.fetch_param(p1);
-                    let p2 = self.fetch_addr(p2);
-                    self.mem[p2] = p0 + p1;
+                    let p0 = self.fetch_param(p0)?;
+                    let p1 = self.fetch_param(p1)?;
+                    let p2 = self.fetch_addr(p2)?;
+                    let res = p0.checked_add(p1).ok_or(Stop::Overflow { ip })?;
+                    self.write(step, p2, res);
                     self.ip += 4;
                 }
                 Instr::Mul(p0, p1, p2) => {
-                    let p0 = self.fetch_param(p0);
-                    let p1 = self.fetch_param(p1);
-                    let p2 = self.fetch_addr(p2);
-                    self.mem[p2] = p0 * p1;
+                    let p0 = self.fetch_param(p0)?;
+                    let p1 = self.fetch_param(p1)?;
+                    let p2 = self.fetch_addr(p2)?;
+                    let res = p0.checked_mul(p1).ok_or(Stop::Overflow { ip })?;
+                    self.write(step, p2, res);
                     self.ip += 4;
                 }
                 Instr::Store(p0) => {
-                    let p0 = self.fetch_addr(p0);
-                    let input = self.input.recv().expect("Processor recv() error");
-                    self.mem[p0] = input;
+                    let p0 = self.fetch_addr(p0)?;
+                    let input = self.read_input()?;
+                    if let Some(step) = step {
+                        step.input = Some(input);
+                    }
+                    self.write(step, p0, input);
                     self.ip += 2;
                 }
                 Instr::Show(p0) => {
-                    let p0 = self.fetch_param(p0);
-                    self.output
-                        .send(p0)
-                        .expect("Processor output channel send error.");
+                    let p0 = self.fetch_param(p0)?;
+                    if let Some(step) = step {
+                        step.output = Some(p0);
+                    }
+                    self.output.send(p0).map_err(|_| Stop::OutputClosed)?;
                     self.ip += 2;
                 }
                 Instr::JmpT(p0, p1) => {
-                    let p0 = self.fetch_param(p0);
+                    let p0 = self.fetch_param(p0)?;
                     if p0 == 0 {
                         self.ip += 3;
                     } else {
-                        let p1 = self.fetch_param(p1);
-                        self.ip = p1.try_into().unwrap();
+                        let p1 = self.fetch_param(p1)?;
+                        self.ip = self.check_addr(p1.into())?;
                     }
                 }
                 Instr::JmpF(p0, p1) => {
-                    let p0 = self.fetch_param(p0);
+                    let p0 = self.fetch_param(p0)?;
                     if p0 == 0 {
-                        let p1 = self.fetch_param(p1);
-                        self.ip = p1.try_into().unwrap();
+                        let p1 = self.fetch_param(p1)?;
+                        self.ip = self.check_addr(p1.into())?;
                     } else {
                         self.ip += 3;
                     }
                 }
                 Instr::CmpLt(p0, p1, p2) => {
-                    let p0 = self.fetch_param(p0);
-                    let p1 = self.fetch_param(p1);
-                    let p2 = self.fetch_addr(p2);
+                    let p0 = self.fetch_param(p0)?;
+                    let p1 = self.fetch_param(p1)?;
+                    let p2 = self.fetch_addr(p2)?;
                     if p0 < p1 {
-                        self.mem[p2] = 1;
+                        self.write(step, p2, 1);
                     } else {
-                        self.mem[p2] = 0;
+                        self.write(step, p2, 0);
                     }
                     self.ip += 4;
                 }
                 Instr::CmpEq(p0, p1, p2) => {
-                    let p0 = self.fetch_param(p0);
-                    let p1 = self.fetch_param(p1);
-                    let p2 = self.fetch_addr(p2);
+                    let p0 = self.fetch_param(p0)?;
+                    let p1 = self.fetch_param(p1)?;
+                    let p2 = self.fetch_addr(p2)?;
                     if p0 == p1 {
-                        self.mem[p2] = 1;
+                        self.write(step, p2, 1);
                     } else {
-                        self.mem[p2] = 0;
+                        self.write(step, p2, 0);
                     }
                     self.ip += 4;
                 }
                 Instr::RBase(p0) => {
-                    let p0 = self.fetch_param(p0);
-                    self.rel_base += p0;
+                    let p0 = self.fetch_param(p0)?;
+                    self.rel_base = self.rel_base.checked_add(p0).ok_or(Stop::Overflow { ip })?;
                     self.ip += 2;
                 }
             }
-            return true;
+            Ok(())
         }
 
-        fn fetch_instruction(&self) -> Instr {
-            if let Some(val) = self.mem.get(self.ip) {
-                let opcode = val % 100;
-                let pcode = val / 100;
+        fn fetch_instruction(&self) -> Result<Instr, Stop> {
+            let ip = self.ip;
+            let val = self.get_address(ip);
+            let opcode = val % 100;
+            let pcode = val / 100;
 
-                match opcode {
-                    1 => {
-                        let p0 = parse_param(0, pcode, self.mem[self.ip + 1]);
-                        let p1 = parse_param(1, pcode, self.mem[self.ip + 2]);
-                        let p2 = parse_param(2, pcode, self.mem[self.ip + 3]);
-                        Instr::Add(p0, p1, p2)
-                    }
-                    2 => {
-                        let p0 = parse_param(0, pcode, self.mem[self.ip + 1]);
-                        let p1 = parse_param(1, pcode, self.mem[self.ip + 2]);
-                        let p2 = parse_param(2, pcode, self.mem[self.ip + 3]);
-                        Instr::Mul(p0, p1, p2)
-                    }
-                    3 => {
-                        let p0 = parse_param(0, pcode, self.mem[self.ip + 1]);
-                        Instr::Store(p0)
-                    }
-                    4 => {
-                        let p0 = parse_param(0, pcode, self.mem[self.ip + 1]);
-                        Instr::Show(p0)
-                    }
-                    5 => {
-                        let p0 = parse_param(0, pcode, self.mem[self.ip + 1]);
-                        let p1 = parse_param(1, pcode, self.mem[self.ip + 2]);
-                        Instr::JmpT(p0, p1)
-                    }
-                    6 => {
-                        let p0 = parse_param(0, pcode, self.mem[self.ip + 1]);
-                        let p1 = parse_param(1, pcode, self.mem[self.ip + 2]);
-                        Instr::JmpF(p0, p1)
-                    }
-                    7 => {
-                        let p0 = parse_param(0, pcode, self.mem[self.ip + 1]);
-                        let p1 = parse_param(1, pcode, self.mem[self.ip + 2]);
-                        let p2 = parse_param(2, pcode, self.mem[self.ip + 3]);
-                        Instr::CmpLt(p0, p1, p2)
-                    }
-                    8 => {
-                        let p0 = parse_param(0, pcode, self.mem[self.ip + 1]);
-                        let p1 = parse_param(1, pcode, self.mem[self.ip + 2]);
-                        let p2 = parse_param(2, pcode, self.mem[self.ip + 3]);
-                        Instr::CmpEq(p0, p1, p2)
-                    }
-                    9 => {
-                        let p0 = parse_param(0, pcode, self.mem[self.ip + 1]);
-                        Instr::RBase(p0)
-                    }
-                    99 => Instr::Halt,
-                    _ => Instr::Unknown,
-                }
+            let nparams = match opcode {
+                1 | 2 | 7 | 8 => 3,
+                3 | 4 | 9 => 1,
+                5 | 6 => 2,
+                99 => return Ok(Instr::Halt),
+                _ => return Err(Stop::InvalidOpcode { ip, opcode: val }),
+            };
+            if pcode < 0 || pcode >= 10_i32.pow(nparams) {
+                return Err(Stop::InvalidMode { ip, opcode: val });
+            }
+            let param = |nth: u32| {
+                parse_param(nth, pcode, self.get_address(ip + 1 + nth as usize))
+                    .ok_or(Stop::InvalidMode { ip, opcode: val })
+            };
+
+            let instr = match opcode {
+                1 => Instr::Add(param(0)?, param(1)?, param(2)?),
+                2 => Instr::Mul(param(0)?, param(1)?, param(2)?),
+                3 => Instr::Store(param(0)?),
+                4 => Instr::Show(param(0)?),
+                5 => Instr::JmpT(param(0)?, param(1)?),
+                6 => Instr::JmpF(param(0)?, param(1)?),
+                7 => Instr::CmpLt(param(0)?, param(1)?, param(2)?),
+                8 => Instr::CmpEq(param(0)?, param(1)?, param(2)?),
+                _ => Instr::RBase(param(0)?),
+            };
+            Ok(instr)
+        }
+
+        // Checks that `addr` can be accessed by the running program.
+        fn check_addr(&self, addr: i64) -> Result<usize, Stop> {
+            if addr < 0 || addr >= self.mem_limit as i64 {
+                Err(Stop::InvalidAddress { ip: self.ip, addr })
             } else {
-                panic!("Invalid memory address");
+                Ok(addr as usize)
             }
         }
 
-        fn fetch_param(&mut self, p: Param) -> i32 {
+        fn fetch_param(&self, p: Param) -> Result<i32, Stop> {
             match p {
-                Param::Immediate(n) => n,
-                Param::Position(n) => {
-                    self.safecheck_memory(n);
-                    self.mem[n]
-                }
+                Param::Immediate(n) => Ok(n),
+                Param::Position(n) => Ok(self.get_address(self.check_addr(n.into())?)),
                 Param::Relative(n) => {
-                    let ind = (self.rel_base + n) as usize;
-                    self.safecheck_memory(ind);
-                    self.mem[ind]
+                    let ind = self.check_addr(i64::from(self.rel_base) + i64::from(n))?;
+                    Ok(self.get_address(ind))
                 }
             }
         }
 
-        fn fetch_addr(&mut self, p: Param) -> usize {
-            let addr: usize = match p {
-                Param::Position(n) => n.try_into().unwrap(),
-                Param::Relative(n) => (self.rel_base + n).try_into().unwrap(),
-                _ => panic!("Invalid paramater!"),
+        fn fetch_addr(&mut self, p: Param) -> Result<usize, Stop> {
+            let addr = match p {
+                Param::Position(n) => self.check_addr(n.into())?,
+                Param::Relative(n) => self.check_addr(i64::from(self.rel_base) + i64::from(n))?,
+                Param::Immediate(_) => {
+                    return Err(Stop::InvalidMode {
+                        ip: self.ip,
+                        opcode: self.get_address(self.ip),
+                    })
+                }
             };
             self.safecheck_memory(addr);
-            return addr;
+            Ok(addr)
         }
     } // END IMPL Processor
+
+    // Patch sets may use every address below the memory limit.
+    impl patch::Memory<i32> for Processor {
+        fn peek(&self, addr: usize) -> Option<i32> {
+            if addr < self.mem_limit {
+                Some(self.get_address(addr))
+            } else {
+                None
+            }
+        }
+
+        fn poke(&mut self, addr: usize, value: i32) -> bool {
+            if addr < self.mem_limit {
+                self.set_address(addr, value);
+                true
+            } else {
+                false
+            }
+        }
+    }
+}
+
+pub mod arcade {
+    use crate::icm::{Processor, Stop};
+    use std::collections::hash_map::Values;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Tile {
+        Empty,
+        Wall,
+        Block,
+        HPaddle,
+        Ball,
+    }
+
+    impl Tile {
+        // The tile with the given id, None if the id is not known.
+        pub fn from_id(id: i32) -> Option<Tile> {
+            match id {
+                0 => Some(Tile::Empty),
+                1 => Some(Tile::Wall),
+                2 => Some(Tile::Block),
+                3 => Some(Tile::HPaddle),
+                4 => Some(Tile::Ball),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    impl Point {
+        pub fn new(x: i32, y: i32) -> Point {
+            Point { x, y }
+        }
+    }
+
+    // Character sets for drawing the screen.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Output {
+        Unicode,
+        Ascii,
+        // Unicode characters colored with ANSI escape codes.
+        Ansi,
+    }
+
+    impl Output {
+        // None stands for a position without a tile.
+        pub fn symbol(self, tile: Option<Tile>) -> char {
+            match (self, tile) {
+                (_, Some(Tile::Empty)) => ' ',
+                (Output::Ascii, None) => '?',
+                (Output::Ascii, Some(Tile::Wall)) => '#',
+                (Output::Ascii, Some(Tile::Block)) => '=',
+                (Output::Ascii, Some(Tile::HPaddle)) => '-',
+                (Output::Ascii, Some(Tile::Ball)) => 'o',
+                (_, None) => '\u{b7}',
+                (_, Some(Tile::Wall)) => '\u{2588}',
+                (_, Some(Tile::Block)) => '\u{2592}',
+                (_, Some(Tile::HPaddle)) => '\u{2015}',
+                (_, Some(Tile::Ball)) => '\u{2022}',
+            }
+        }
+    }
+
+    impl FromStr for Output {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Output, String> {
+            match s.to_lowercase().as_str() {
+                "unicode" => Ok(Output::Unicode),
+                "ascii" => Ok(Output::Ascii),
+                "ansi" => Ok(Output::Ansi),
+                _ => Err(format!(
+                    "unknown output '{}', expected unicode, ascii or ansi",
+                    s
+                )),
+            }
+        }
+    }
+
+    const ANSI_RESET: &str = "\x1b[0m";
+
+    fn ansi_color(tile: Option<Tile>) -> &'static str {
+        match tile {
+            None => "\x1b[2m",
+            Some(Tile::Empty) => ANSI_RESET,
+            Some(Tile::Wall) => "\x1b[37m",
+            Some(Tile::Block) => "\x1b[33m",
+            Some(Tile::HPaddle) => "\x1b[36m",
+            Some(Tile::Ball) => "\x1b[1;31m",
+        }
+    }
+
+    /*
+    Runs the game program synchronously. Every time the program asks for
+    joystick input a frame is complete and the screen is up to date.
+     */
+    pub struct Arcade {
+        cpu: Processor,
+        joystick: Sender<i32>,
+        screen: Receiver<i32>,
+        pending: Vec<i32>,
+        tiles: HashMap<Point, Tile>,
+        display: i32,
+        ball: Option<Point>,
+        paddle: Option<Point>,
+        blocks: usize,
+        destroyed: usize,
+        frames: usize,
+        awaiting_input: bool,
+        joystick_log: Vec<i32>,
+    }
+
+    impl Arcade {
+        pub fn new(program: &[i32]) -> Arcade {
+            let (joystick, recv_cpu) = channel();
+            let (send_screen, screen) = channel();
+            let mut cpu = Processor::new(0, program.to_vec(), recv_cpu, send_screen);
+            cpu.set_wait_for_input(false);
+            Arcade {
+                cpu,
+                joystick,
+                screen,
+                pending: vec![],
+                tiles: HashMap::new(),
+                display: 0,
+                ball: None,
+                paddle: None,
+                blocks: 0,
+                destroyed: 0,
+                frames: 0,
+                awaiting_input: false,
+                joystick_log: vec![],
+            }
+        }
+
+        // Gives access to the processor, e.g. to patch the program before it runs.
+        pub fn cpu_mut(&mut self) -> &mut Processor {
+            &mut self.cpu
+        }
+
+        pub fn tiles(&self) -> Values<'_, Point, Tile> {
+            self.tiles.values()
+        }
+
+        pub fn tile_map(&self) -> &HashMap<Point, Tile> {
+            &self.tiles
+        }
+
+        pub fn display(&self) -> i32 {
+            self.display
+        }
+
+        pub fn ball(&self) -> Option<Point> {
+            self.ball
+        }
+
+        pub fn paddle(&self) -> Option<Point> {
+            self.paddle
+        }
+
+        pub fn blocks(&self) -> usize {
+            self.blocks
+        }
+
+        // Number of blocks which were drawn once and then removed.
+        pub fn destroyed(&self) -> usize {
+            self.destroyed
+        }
+
+        // All joystick positions sent so far, one per frame.
+        pub fn joystick_log(&self) -> &[i32] {
+            &self.joystick_log
+        }
+
+        // Number of completed frames.
+        pub fn frames(&self) -> usize {
+            self.frames
+        }
+
+        // Smallest and largest coordinates of all tiles drawn so far.
+        pub fn bounds(&self) -> Option<(Point, Point)> {
+            let min_x = self.tiles.keys().map(|p| p.x).min()?;
+            let max_x = self.tiles.keys().map(|p| p.x).max()?;
+            let min_y = self.tiles.keys().map(|p| p.y).min()?;
+            let max_y = self.tiles.keys().map(|p| p.y).max()?;
+            Some((Point::new(min_x, min_y), Point::new(max_x, max_y)))
+        }
+
+        pub fn print_tiles(&self) -> String {
+            self.render(Output::Unicode)
+        }
+
+        /*
+        Draws the screen within the bounds of the tile map. Positions the
+        program has not drawn yet are shown as a placeholder.
+         */
+        pub fn render(&self, output: Output) -> String {
+            let mut s = String::new();
+            let (min, max) = match self.bounds() {
+                Some(b) => b,
+                None => return s,
+            };
+            for y in min.y..=max.y {
+                let mut color = None;
+                for x in min.x..=max.x {
+                    let tile = self.tiles.get(&Point::new(x, y)).copied();
+                    if output == Output::Ansi && color != Some(tile) {
+                        s.push_str(ansi_color(tile));
+                        color = Some(tile);
+                    }
+                    s.push(output.symbol(tile));
+                }
+                if color.is_some() {
+                    s.push_str(ANSI_RESET);
+                }
+                s.push('\n');
+            }
+            s
+        }
+
+        /*
+        Runs the program until it waits for the joystick or stops otherwise.
+        Stop::InputRequired marks the end of a frame. Without moving the joystick
+        the next frame does not start. An unknown tile id stops the frame with
+        Stop::InvalidTile, the tiles drawn before it are kept.
+         */
+        pub fn run_frame(&mut self) -> Stop {
+            if self.awaiting_input {
+                return Stop::InputRequired;
+            }
+            let stop = self.cpu.run();
+            self.pending.extend(self.screen.try_iter());
+            let mut i = 0;
+            while i + 3 <= self.pending.len() {
+                let (x, y, val) = (self.pending[i], self.pending[i + 1], self.pending[i + 2]);
+                i += 3;
+                if x == -1 && y == 0 {
+                    self.display = val;
+                } else if let Some(tile) = Tile::from_id(val) {
+                    self.set_tile(Point::new(x, y), tile);
+                } else {
+                    self.pending.drain(..i);
+                    return Stop::InvalidTile { x, y, id: val };
+                }
+            }
+            self.pending.drain(..i);
+            if stop == Stop::InputRequired {
+                self.frames += 1;
+                self.awaiting_input = true;
+            }
+            stop
+        }
+
+        // Sets the joystick for the next frame: -1 left, 0 neutral, 1 right.
+        pub fn move_joystick(&mut self, pos: i32) {
+            self.joystick.send(pos).expect("Could not send JS position");
+            self.awaiting_input = false;
+            self.joystick_log.push(pos);
+        }
+
+        // Joystick position which moves the paddle towards the ball.
+        pub fn follow_ball(&self) -> i32 {
+            match (self.paddle, self.ball) {
+                (Some(p), Some(b)) => (b.x - p.x).signum(),
+                _ => 0,
+            }
+        }
+
+        // Plays until the game is over by moving the paddle towards the ball.
+        pub fn play(&mut self) -> Stop {
+            self.play_with(&mut Follower).stop
+        }
+
+        // Plays until the game is over or the strategy gives up.
+        pub fn play_with<S: JoystickStrategy + ?Sized>(&mut self, strategy: &mut S) -> Report {
+            let mut moves = 0;
+            let stop = loop {
+                let stop = self.run_frame();
+                if stop != Stop::InputRequired {
+                    break stop;
+                }
+                let pos = match strategy.joystick(self) {
+                    Some(pos) => pos,
+                    None => break stop,
+                };
+                if pos != 0 {
+                    moves += 1;
+                }
+                self.move_joystick(pos);
+            };
+            Report {
+                stop,
+                frames: self.frames,
+                moves,
+                score: self.display,
+                destroyed: self.destroyed,
+                blocks: self.blocks,
+            }
+        }
+
+        fn set_tile(&mut self, p: Point, tile: Tile) {
+            let prev = self.tiles.insert(p, tile);
+            if prev == Some(Tile::Block) {
+                self.blocks -= 1;
+                if tile != Tile::Block {
+                    self.destroyed += 1;
+                }
+            }
+            if prev == Some(Tile::Ball) && self.ball == Some(p) {
+                self.ball = None;
+            }
+            if prev == Some(Tile::HPaddle) && self.paddle == Some(p) {
+                self.paddle = None;
+            }
+            match tile {
+                Tile::Block => self.blocks += 1,
+                Tile::Ball => self.ball = Some(p),
+                Tile::HPaddle => self.paddle = Some(p),
+                _ => (),
+            }
+        }
+    }
+
+    // Result of a game played with a joystick strategy.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Report {
+        pub stop: Stop,
+        pub frames: usize,
+        // Frames in which the joystick was not neutral.
+        pub moves: usize,
+        pub score: i32,
+        pub destroyed: usize,
+        // Blocks left on the screen.
+        pub blocks: usize,
+    }
+
+    /*
+    Decides the joystick position at the end of every frame: -1 left, 0 neutral,
+    1 right. Returning None ends the game early.
+     */
+    pub trait JoystickStrategy {
+        fn name(&self) -> &str;
+        fn joystick(&mut self, arcade: &Arcade) -> Option<i32>;
+    }
+
+    // Moves the paddle to the current x position of the ball.
+    pub struct Follower;
+
+    impl JoystickStrategy for Follower {
+        fn name(&self) -> &str {
+            "follower"
+        }
+
+        fn joystick(&mut self, arcade: &Arcade) -> Option<i32> {
+            Some(arcade.follow_ball())
+        }
+    }
+
+    /*
+    Moves the paddle to where the falling ball will reach the paddle row. The
+    ball's direction is taken from its last two positions, bounces off walls
+    are taken into account, bounces off blocks are not.
+     */
+    pub struct Predictive {
+        last_ball: Option<Point>,
+    }
+
+    impl Predictive {
+        pub fn new() -> Predictive {
+            Predictive { last_ball: None }
+        }
+
+        fn landing(arcade: &Arcade, ball: Point, dx: i32, row: i32) -> i32 {
+            let (mut x, mut y, mut dx) = (ball.x, ball.y, dx);
+            while y < row {
+                if arcade.tile_map().get(&Point::new(x + dx, y + 1)) == Some(&Tile::Wall) {
+                    dx = -dx;
+                }
+                x += dx;
+                y += 1;
+            }
+            x
+        }
+    }
+
+    impl Default for Predictive {
+        fn default() -> Self {
+            Predictive::new()
+        }
+    }
+
+    impl JoystickStrategy for Predictive {
+        fn name(&self) -> &str {
+            "predictive"
+        }
+
+        fn joystick(&mut self, arcade: &Arcade) -> Option<i32> {
+            let (ball, paddle) = match (arcade.ball(), arcade.paddle()) {
+                (Some(b), Some(p)) => (b, p),
+                _ => return Some(0),
+            };
+            let last = self.last_ball.replace(ball).unwrap_or(ball);
+            let (dx, dy) = (ball.x - last.x, ball.y - last.y);
+            let target = if dy > 0 && dx != 0 && ball.y < paddle.y {
+                Predictive::landing(arcade, ball, dx, paddle.y - 1)
+            } else {
+                ball.x
+            };
+            Some((target - paddle.x).signum())
+        }
+    }
+
+    // Moves the joystick randomly. The same seed always plays the same game.
+    pub struct RandomStrategy {
+        state: u64,
+    }
+
+    impl RandomStrategy {
+        pub fn new(seed: u64) -> RandomStrategy {
+            RandomStrategy { state: seed.max(1) }
+        }
+    }
+
+    impl JoystickStrategy for RandomStrategy {
+        fn name(&self) -> &str {
+            "random"
+        }
+
+        fn joystick(&mut self, _arcade: &Arcade) -> Option<i32> {
+            // xorshift64
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            Some((self.state % 3) as i32 - 1)
+        }
+    }
+
+    // Replays recorded joystick positions, e.g. from Arcade::joystick_log().
+    // Stops the game when the recording ends.
+    pub struct Replay {
+        moves: Vec<i32>,
+        next: usize,
+    }
+
+    impl Replay {
+        pub fn new(moves: Vec<i32>) -> Replay {
+            Replay { moves, next: 0 }
+        }
+    }
+
+    impl JoystickStrategy for Replay {
+        fn name(&self) -> &str {
+            "replay"
+        }
+
+        fn joystick(&mut self, _arcade: &Arcade) -> Option<i32> {
+            let pos = self.moves.get(self.next).copied();
+            self.next += 1;
+            pos
+        }
+    }
+}
+
+pub mod recorder {
+    use crate::arcade::{Arcade, Point, Tile};
+    use image::gif::{Encoder, Frame};
+    use image::ImageError;
+    use std::convert::TryFrom;
+    use std::fmt;
+    use std::io::Write;
+
+    // Colors of the tiles, indexed by tile id.
+    pub const DEFAULT_PALETTE: [[u8; 3]; 5] = [
+        [0, 0, 0],       // Empty
+        [128, 128, 128], // Wall
+        [200, 90, 40],   // Block
+        [60, 160, 255],  // HPaddle
+        [255, 255, 255], // Ball
+    ];
+
+    #[derive(Debug)]
+    pub enum Error {
+        Image(ImageError),
+        // Tiles need at least one pixel.
+        ZeroScale,
+        // A GIF frame is at most 65535 pixels wide and high.
+        TooLarge { width: usize, height: usize },
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Error::Image(e) => write!(f, "{}", e),
+                Error::ZeroScale => write!(f, "the scale has to be at least 1"),
+                Error::TooLarge { width, height } => write!(
+                    f,
+                    "a frame of {}x{} pixels is too large for a GIF",
+                    width, height
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    impl From<ImageError> for Error {
+        fn from(e: ImageError) -> Error {
+            Error::Image(e)
+        }
+    }
+
+    /*
+    Writes the frames of an arcade game as an animated GIF. Every tile is drawn
+    as a block of scale x scale pixels. The image size is fixed by the tiles of
+    the first captured frame, as all frames of a GIF share one screen. Tiles
+    drawn later outside of these bounds are not recorded.
+     */
+    pub struct GifRecorder<W: Write> {
+        encoder: Encoder<W>,
+        scale: usize,
+        delay: u16,
+        palette: [[u8; 3]; 5],
+        bounds: Option<(Point, Point)>,
+        frames: usize,
+    }
+
+    impl<W: Write> GifRecorder<W> {
+        pub fn new(w: W, scale: usize) -> Result<GifRecorder<W>, Error> {
+            if scale == 0 {
+                return Err(Error::ZeroScale);
+            }
+            Ok(GifRecorder {
+                encoder: Encoder::new(w),
+                scale,
+                delay: 3,
+                palette: DEFAULT_PALETTE,
+                bounds: None,
+                frames: 0,
+            })
+        }
+
+        // Sets the time each frame is shown, in hundredths of a second.
+        pub fn set_delay(&mut self, delay: u16) {
+            self.delay = delay;
+        }
+
+        pub fn set_color(&mut self, tile: Tile, rgb: [u8; 3]) {
+            self.palette[tile as usize] = rgb;
+        }
+
+        // Number of captured frames.
+        pub fn frames(&self) -> usize {
+            self.frames
+        }
+
+        // Appends the current screen of the arcade to the animation.
+        pub fn capture(&mut self, arcade: &Arcade) -> Result<(), Error> {
+            let tiles = arcade.tile_map();
+            if self.bounds.is_none() {
+                let origin = Point::new(0, 0);
+                self.bounds = Some(arcade.bounds().unwrap_or((origin, origin)));
+            }
+            let (min, max) = self.bounds.unwrap();
+            let width = ((max.x - min.x + 1) as usize).saturating_mul(self.scale);
+            let height = ((max.y - min.y + 1) as usize).saturating_mul(self.scale);
+            let too_large = |_| Error::TooLarge { width, height };
+            let gif_width = u16::try_from(width).map_err(too_large)?;
+            let gif_height = u16::try_from(height).map_err(too_large)?;
+
+            let mut pixels = vec![0; width * height];
+            for (i, px) in pixels.iter_mut().enumerate() {
+                let x = (i % width / self.scale) as i32 + min.x;
+                let y = (i / width / self.scale) as i32 + min.y;
+                if let Some(tile) = tiles.get(&Point::new(x, y)) {
+                    *px = *tile as u8;
+                }
+            }
+            let palette = self.palette.iter().flatten().copied().collect::<Vec<_>>();
+            let mut frame =
+                Frame::from_palette_pixels(gif_width, gif_height, &pixels, &palette, None);
+            frame.delay = self.delay;
+
+            self.encoder.encode(&frame)?;
+            self.frames += 1;
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn test_step_back() {
+    use icm::{Processor, Stop};
+    use std::sync::mpsc::channel;
+
+    let program = vec![3, 9, 1, 9, 10, 9, 4, 9, 99, 0, 5];
+    let (send_cpu, recv_cpu) = channel();
+    let (send_out, recv_out) = channel();
+    let mut cpu = Processor::new(0, program.clone(), recv_cpu, send_out);
+    cpu.enable_history();
+
+    send_cpu.send(7).unwrap();
+    cpu.run();
+    assert_eq!(recv_out.recv().unwrap(), 12);
+    assert_eq!(cpu.history().len(), 3);
+
+    let out = cpu.rewind_to_output().unwrap();
+    assert_eq!((out.ip, out.output), (6, Some(12)));
+
+    let add = cpu.rewind_to_write(9).unwrap();
+    assert_eq!(add.ip, 2);
+    assert_eq!(cpu.get_address(9), 7);
+
+    let store = cpu.rewind_to_write(9).unwrap();
+    assert_eq!((store.ip, store.input), (0, Some(7)));
+    assert_eq!(cpu.get_address(9), 0);
+    assert_eq!(cpu.ip(), 0);
+    assert!(cpu.step_back().is_none());
+
+    // the consumed input is replayed, nothing new has to be sent
+    cpu.run();
+    assert_eq!(recv_out.recv().unwrap(), 12);
+
+    // a store to a new address which has to wait for input does not keep the
+    // grown memory, undoing the store restores the original size
+    let (send_cpu, recv_cpu) = channel();
+    let (send_out, _recv_out) = channel();
+    let mut cpu = Processor::new(0, vec![3, 100, 99], recv_cpu, send_out);
+    cpu.enable_history();
+    cpu.set_wait_for_input(false);
+    assert_eq!(cpu.run(), Stop::InputRequired);
+    send_cpu.send(5).unwrap();
+    assert_eq!(cpu.run(), Stop::Halt);
+    assert_eq!(cpu.get_address(100), 5);
+    assert_eq!(cpu.history()[0].mem_len, 3);
+    let store = cpu.step_back().unwrap();
+    assert_eq!(store.write, Some((100, 0)));
+    assert_eq!(cpu.get_address(100), 0);
+}
+
+#[test]
+fn test_patch_set() {
+    use icm::Processor;
+    use patch::{AddressError, PatchSet};
+    use std::sync::mpsc::channel;
+
+    let mut set = PatchSet::parse("lhs @5 = 3\nrhs @6 = 4\nresult @0").unwrap();
+
+    let (_send_cpu, recv_cpu) = channel();
+    let (send_out, _recv_out) = channel();
+    let program = vec![2, 5, 6, 0, 99, 0, 0];
+    let mut cpu = Processor::new(0, program.clone(), recv_cpu, send_out);
+    set.apply(&mut cpu).unwrap();
+    cpu.run();
+    assert_eq!(set.read(&cpu).unwrap()[2], ("result".to_string(), 12));
+
+    assert!(set.set("rhs", 5));
+    cpu.reset();
+    cpu.load_into_memory(&program);
+    set.apply(&mut cpu).unwrap();
+    cpu.run();
+    assert_eq!(cpu.get_address(0), 15);
+
+    // the memory grows up to its limit
+    cpu.set_memory_limit(100);
+    let far = PatchSet::parse("far @99 = 1\nbeyond @100 = 2").unwrap();
+    assert_eq!(
+        far.apply(&mut cpu),
+        Err(AddressError {
+            name: "beyond".to_string(),
+            addr: 100
+        })
+    );
+    assert_eq!(cpu.get_address(99), 1);
+}
+
+#[test]
+fn test_arcade_frames() {
+    use arcade::{Arcade, Point, Tile};
+    use icm::Stop;
+
+    // Draws two blocks, a paddle and a ball, then per frame reads the joystick,
+    // moves the ball one to the right, clears a block and shows the score.
+    let program = vec![
+        104, 0, 104, 0, 104, 2, // (0, 0) block
+        104, 1, 104, 0, 104, 2, // (1, 0) block
+        104, 1, 104, 2, 104, 3, // (1, 2) paddle
+        104, 0, 104, 1, 104, 4, // (0, 1) ball
+        3, 100, // read joystick
+        104, 0, 104, 1, 104, 0, // (0, 1) empty
+        104, 1, 104, 1, 104, 4, // (1, 1) ball
+        104, 0, 104, 0, 104, 0, // (0, 0) empty
+        104, -1, 104, 0, 104, 42, // score
+        3, 100, // read joystick
+        99,
+    ];
+    let mut arcade = Arcade::new(&program);
+
+    assert_eq!(arcade.run_frame(), Stop::InputRequired);
+    assert_eq!(arcade.frames(), 1);
+    assert_eq!(arcade.blocks(), 2);
+    assert_eq!(arcade.ball(), Some(Point::new(0, 1)));
+    assert_eq!(arcade.paddle(), Some(Point::new(1, 2)));
+
+    // no joystick input, the frame does not advance
+    assert_eq!(arcade.run_frame(), Stop::InputRequired);
+    assert_eq!(arcade.frames(), 1);
+
+    arcade.move_joystick(1);
+    assert_eq!(arcade.run_frame(), Stop::InputRequired);
+    assert_eq!(arcade.ball(), Some(Point::new(1, 1)));
+    assert_eq!(arcade.blocks(), 1);
+    assert_eq!(arcade.display(), 42);
+    assert_eq!(arcade.tile_map()[&Point::new(0, 1)], Tile::Empty);
+
+    assert_eq!(arcade.play(), Stop::Halt);
+    assert_eq!(arcade.frames(), 2);
+
+    // tile id 7 does not exist, the wall before it is still drawn
+    let mut arcade = Arcade::new(&[104, 0, 104, 0, 104, 1, 104, 1, 104, 0, 104, 7, 99]);
+    assert_eq!(arcade.run_frame(), Stop::InvalidTile { x: 1, y: 0, id: 7 });
+    assert_eq!(arcade.tile_map()[&Point::new(0, 0)], Tile::Wall);
+    assert_eq!(arcade.tile_map().len(), 1);
+    assert_eq!(arcade.frames(), 0);
+    assert_eq!(Tile::from_id(4), Some(Tile::Ball));
+    assert_eq!(Tile::from_id(-1), None);
+}
+
+#[test]
+fn test_render() {
+    use arcade::{Arcade, Output, Point};
+
+    // wall at (-2, 0), ball at (0, 1), nothing drawn in between
+    let program = vec![
+        104, -2, 104, 0, 104, 1, //
+        104, 0, 104, 1, 104, 4, //
+        99,
+    ];
+    let mut arcade = Arcade::new(&program);
+    assert_eq!(arcade.render(Output::Ascii), "");
+    arcade.run_frame();
+
+    assert_eq!(arcade.bounds(), Some((Point::new(-2, 0), Point::new(0, 1))));
+    assert_eq!(arcade.render(Output::Ascii), "#??\n??o\n");
+    assert_eq!(
+        arcade.print_tiles(),
+        "\u{2588}\u{b7}\u{b7}\n\u{b7}\u{b7}\u{2022}\n"
+    );
+    assert_eq!(
+        arcade.render(Output::Ansi),
+        "\x1b[37m\u{2588}\x1b[2m\u{b7}\u{b7}\x1b[0m\n\x1b[2m\u{b7}\u{b7}\x1b[1;31m\u{2022}\x1b[0m\n"
+    );
+    assert_eq!("ANSI".parse::<Output>(), Ok(Output::Ansi));
+    assert!("vga".parse::<Output>().is_err());
+}
+
+#[test]
+fn test_strategies() {
+    use arcade::{Arcade, Follower, JoystickStrategy, Predictive, RandomStrategy, Replay};
+    use icm::Stop;
+
+    // Reads the joystick three times, moves the ball from (1, 0) over (2, 1)
+    // to (3, 2) and clears the block at (3, 0), then halts.
+    let program = vec![
+        104, 3, 104, 0, 104, 2, // (3, 0) block
+        104, 2, 104, 4, 104, 3, // (2, 4) paddle
+        104, 1, 104, 0, 104, 4, // (1, 0) ball
+        3, 100, //
+        104, 2, 104, 1, 104, 4, // (2, 1) ball
+        3, 100, //
+        104, 3, 104, 2, 104, 4, // (3, 2) ball
+        104, 3, 104, 0, 104, 0, // (3, 0) empty
+        104, -1, 104, 0, 104, 7, // score
+        3, 100, //
+        99,
+    ];
+
+    let mut arcade = Arcade::new(&program);
+    let report = arcade.play_with(&mut Follower);
+    assert_eq!(report.stop, Stop::Halt);
+    assert_eq!((report.frames, report.moves), (3, 2));
+    assert_eq!((report.score, report.destroyed, report.blocks), (7, 1, 0));
+    assert_eq!(arcade.joystick_log(), &[-1, 0, 1]);
+
+    // the ball falls right and reaches row 3 at x = 4
+    let mut predictive = Predictive::new();
+    let mut arcade = Arcade::new(&program);
+    arcade.run_frame();
+    assert_eq!(predictive.joystick(&arcade), Some(-1));
+    arcade.move_joystick(0);
+    arcade.run_frame();
+    assert_eq!(predictive.joystick(&arcade), Some(1));
+
+    let mut replay = Replay::new(vec![1, 1]);
+    let report = Arcade::new(&program).play_with(&mut replay);
+    assert_eq!(report.stop, Stop::InputRequired);
+    assert_eq!((report.frames, report.moves, report.score), (3, 2, 7));
+
+    let moves = |seed| {
+        let mut arcade = Arcade::new(&program);
+        arcade.play_with(&mut RandomStrategy::new(seed));
+        arcade.joystick_log().to_vec()
+    };
+    assert_eq!(moves(42), moves(42));
+    assert!(moves(1).iter().all(|m| (-1..=1).contains(m)));
+}
+
+#[test]
+fn test_gif_recorder() {
+    use arcade::{Arcade, Tile};
+    use image::gif::Decoder;
+    use image::{AnimationDecoder, Rgba};
+    use recorder::{Error, GifRecorder, DEFAULT_PALETTE};
+
+    // a 2x1 screen: wall and ball, then wall and paddle
+    let program = vec![
+        104, 0, 104, 0, 104, 1, 104, 1, 104, 0, 104, 4, 3, 100, //
+        104, 1, 104, 0, 104, 3, 99,
+    ];
+    let mut buf = vec![];
+    {
+        let mut recorder = GifRecorder::new(&mut buf, 2).unwrap();
+        recorder.set_color(Tile::Wall, [10, 20, 30]);
+        let mut arcade = Arcade::new(&program);
+        arcade.run_frame();
+        recorder.capture(&arcade).unwrap();
+        arcade.move_joystick(0);
+        arcade.run_frame();
+        recorder.capture(&arcade).unwrap();
+        assert_eq!(recorder.frames(), 2);
+    }
+
+    let frames = Decoder::new(&buf[..])
+        .unwrap()
+        .into_frames()
+        .collect_frames()
+        .unwrap();
+    assert_eq!(frames.len(), 2);
+    let rgba = |c: [u8; 3]| Rgba([c[0], c[1], c[2], 255]);
+    let first = frames[0].buffer();
+    assert_eq!(first.dimensions(), (4, 2));
+    assert_eq!(*first.get_pixel(1, 1), rgba([10, 20, 30]));
+    assert_eq!(
+        *first.get_pixel(2, 0),
+        rgba(DEFAULT_PALETTE[Tile::Ball as usize])
+    );
+    assert_eq!(
+        *frames[1].buffer().get_pixel(3, 1),
+        rgba(DEFAULT_PALETTE[Tile::HPaddle as usize])
+    );
+
+    match GifRecorder::new(vec![], 0) {
+        Err(Error::ZeroScale) => (),
+        r => panic!("unexpected result: {:?}", r.map(|r| r.frames())),
+    }
+    // 2x1 tiles of 40000 pixels do not fit into a GIF frame
+    let mut recorder = GifRecorder::new(vec![], 40000).unwrap();
+    let mut arcade = Arcade::new(&program);
+    arcade.run_frame();
+    match recorder.capture(&arcade) {
+        Err(Error::TooLarge {
+            width: 80000,
+            height: 40000,
+        }) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert_eq!(recorder.frames(), 0);
 }
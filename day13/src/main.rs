@@ -1,204 +1,157 @@
-use day13::icm::Processor;
-use std::collections::hash_map::Values;
-use std::collections::HashMap;
-use std::sync::mpsc::channel;
-use std::sync::mpsc::{Receiver, Sender};
-use std::sync::{Arc, Mutex};
+use day13::arcade::{
+    Arcade, Follower, JoystickStrategy, Output, Predictive, RandomStrategy, Replay,
+};
+use day13::icm::Stop;
+use day13::recorder::GifRecorder;
+use patch::PatchSet;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
 use std::thread;
 use std::time::Duration;
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::{async_stdin, clear, cursor};
 
-#[derive(Debug, PartialEq, Eq)]
-enum Tile {
-    Empty,
-    Wall,
-    Block,
-    HPaddle,
-    Ball,
-}
-
-impl Tile {
-    fn from_id(id: i32) -> Tile {
-        match id {
-            0 => Tile::Empty,
-            1 => Tile::Wall,
-            2 => Tile::Block,
-            3 => Tile::HPaddle,
-            4 => Tile::Ball,
-            _ => panic!("Tile parsing error: Unknown Tile ID."),
-        }
-    }
-}
+const FRAME_TIME: Duration = Duration::from_millis(80);
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
-struct Point {
-    x: i32,
-    y: i32,
-}
-
-impl Point {
-    fn new(x: i32, y: i32) -> Point {
-        Point { x, y }
+fn parse_input(s: &str) -> Vec<i32> {
+    let mut v = vec![];
+    for n in s.split(',') {
+        let n = match n.trim().parse::<i32>() {
+            Err(e) => panic!("Could not parse {}: {}", n, e),
+            Ok(i) => i,
+        };
+        v.push(n);
     }
+    v
 }
 
-struct Arcade {
-    tiles: HashMap<Point, Tile>,
-    joystick_pos: i32,
-    display: i32,
-    inp: Receiver<i32>,
-    out: Sender<i32>,
-}
-
-impl Arcade {
-    fn new(inp: Receiver<i32>, out: Sender<i32>) -> Arcade {
-        Arcade {
-            tiles: HashMap::new(),
-            joystick_pos: 0,
-            display: 0,
-            inp,
-            out,
+/*
+Plays the game in the terminal, one frame every FRAME_TIME.
+human  - The paddle is moved with the arrow keys (or a/d) instead of the AI.
+output - Characters used to draw the screen.
+ */
+fn play_interactive(arcade: &mut Arcade, human: bool, output: Output) -> Stop {
+    let stdout = io::stdout();
+    let mut stdout = stdout
+        .lock()
+        .into_raw_mode()
+        .expect("Could not switch terminal to raw mode.");
+    let mut keys = async_stdin().keys();
+    let help = if human {
+        "Left/Right or a/d: move paddle, q: quit"
+    } else {
+        "q: quit"
+    };
+
+    write!(stdout, "{}", cursor::Hide).unwrap();
+    let stop = loop {
+        let stop = arcade.run_frame();
+        write!(
+            stdout,
+            "{}{}{}\r\nScore: {}  Blocks: {}\r\n{}\r\n",
+            clear::All,
+            cursor::Goto(1, 1),
+            arcade.render(output).replace('\n', "\r\n"),
+            arcade.display(),
+            arcade.blocks(),
+            help
+        )
+        .unwrap();
+        stdout.flush().unwrap();
+        if stop != Stop::InputRequired {
+            break stop;
         }
-    }
 
-    fn reset(&mut self) {
-        self.tiles.clear();
-        self.joystick_pos = 0;
-        self.display = 0;
-    }
-
-    fn tiles(&self) -> Values<Point, Tile> {
-        self.tiles.values()
-    }
-
-    fn display(&self) -> i32 {
-        self.display
-    }
-
-    fn get_ball_pos(&self) -> Option<Point> {
-        self.tiles
-            .iter()
-            .filter(|(_k, v)| **v == Tile::Ball)
-            .map(|(k, _)| *k)
-            .next()
-    }
-
-    fn get_paddle_pos(&self) -> Option<Point> {
-        self.tiles
-            .iter()
-            .filter(|(_k, v)| **v == Tile::HPaddle)
-            .map(|(k, _)| *k)
-            .next()
-    }
-
-    fn print_tiles(&self) -> String {
-        let mut s = String::new();
-        for j in 0..20 {
-            for i in 0..50 {
-                if let Some(tile) = self.tiles.get(&Point { x: i, y: j }) {
-                    let c = match tile {
-                        Tile::Empty => ' ',
-                        Tile::Ball => '\u{2022}',
-                        Tile::Block => '\u{2592}',
-                        Tile::HPaddle => '\u{2015}',
-                        Tile::Wall => '\u{2588}',
-                    };
-                    s.push(c);
-                }
+        thread::sleep(FRAME_TIME);
+        // the last key pressed during the frame wins
+        let mut pos = 0;
+        let mut quit = false;
+        while let Some(Ok(key)) = keys.next() {
+            match key {
+                Key::Left | Key::Char('a') => pos = -1,
+                Key::Right | Key::Char('d') => pos = 1,
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') => quit = true,
+                _ => (),
             }
-            s.push('\n');
         }
-        return s;
-    }
-
-    fn init(&mut self) {
-        loop {
-            if let Some((x, y, tid)) = self.read_three() {
-                self.tiles.insert(Point::new(x, y), Tile::from_id(tid));
-            } else {
-                break;
-            }
+        if quit {
+            break stop;
         }
-    }
-
-    fn play(&mut self) {
-        let mut paddle = self.get_paddle_pos();
-        let mut ball = self.get_ball_pos();
-        let mut prev_ball = None;
-
-        loop {
-            if let Some(p) = paddle {
-                if ball != prev_ball {
-                    if let Some(b) = ball {
-                        if p.x < b.x {
-                            self.joystick_pos = 1;
-                        } else if p.x > b.x {
-                            self.joystick_pos = -1;
-                        } else {
-                            self.joystick_pos = 0;
-                        }
-                        self.out
-                            .send(self.joystick_pos)
-                            .expect("Could not send JS position");
-                    } else {
-                        self.joystick_pos = 0;
-                    }
-                    prev_ball = ball;
-                    // print!("\x1B[2J");
-                    // println!("{}", self.print_tiles());
-                    // println!(
-                    //     "Blocks: {}",
-                    //     self.tiles().filter(|t| **t == Tile::Block).count()
-                    // );
-                    // println!("Score: {}", self.display());
-                }
-            }
-
-            while let Some((x, y, tid)) = self.read_three() {
-                if x == -1 && y == 0 {
-                    self.display = tid;
-                } else {
-                    self.tiles.insert(Point::new(x, y), Tile::from_id(tid));
-                }
-            }
-
-            if self.tiles().filter(|t| **t == Tile::Block).count() == 0 {
-                break;
-            }
-
-            paddle = self.get_paddle_pos();
-            ball = self.get_ball_pos();
+        if !human {
+            pos = arcade.follow_ball();
         }
-    }
-
-    fn read_three(&self) -> Option<(i32, i32, i32)> {
-        let timeout = Duration::from_millis(100);
-        let one = match self.inp.recv_timeout(timeout) {
-            Ok(v) => v,
-            _ => return None,
-        };
-        let two = match self.inp.recv_timeout(timeout) {
-            Ok(v) => v,
-            _ => return None,
-        };
-        let three = match self.inp.recv_timeout(timeout) {
-            Ok(v) => v,
-            _ => return None,
-        };
+        arcade.move_joystick(pos);
+    };
+    write!(stdout, "{}", cursor::Show).unwrap();
+    stop
+}
 
-        Some((one, two, three))
+/*
+Plays the game with the follower AI and writes every frame to a GIF.
+path  - Output file.
+scale - Size of a tile in pixels.
+ */
+fn record_game(program: &[i32], free_play: &PatchSet<i32>, path: &str, scale: usize) {
+    let file = File::create(path).expect("Could not create the GIF file.");
+    let mut recorder =
+        GifRecorder::new(BufWriter::new(file), scale).expect("Could not create the recorder.");
+    let mut arcade = Arcade::new(program);
+    free_play
+        .apply(arcade.cpu_mut())
+        .expect("Could not apply the free play patch");
+
+    let mut strategy = Follower;
+    loop {
+        let stop = arcade.run_frame();
+        recorder.capture(&arcade).expect("Could not write frame.");
+        if stop != Stop::InputRequired {
+            println!("Recorded {} frames to {}.", recorder.frames(), path);
+            println!("Final Score: {}", arcade.display());
+            return;
+        }
+        let pos = strategy.joystick(&arcade).unwrap_or(0);
+        arcade.move_joystick(pos);
     }
 }
 
-fn parse_input(s: &str) -> Vec<i32> {
-    let mut v = vec![];
-    for n in s.split(',') {
-        let n = match n.trim().parse::<i32>() {
-            Err(e) => panic!("Could not parse {}: {}", n, e),
-            Ok(i) => i,
-        };
-        v.push(n);
+fn compare_strategies(program: &[i32], free_play: &PatchSet<i32>) {
+    // the replay plays back the moves of the follower
+    let mut recording = Arcade::new(program);
+    free_play
+        .apply(recording.cpu_mut())
+        .expect("Could not apply the free play patch");
+    recording.play();
+
+    let strategies: Vec<Box<dyn JoystickStrategy>> = vec![
+        Box::new(Follower),
+        Box::new(Predictive::new()),
+        Box::new(RandomStrategy::new(2019)),
+        Box::new(Replay::new(recording.joystick_log().to_vec())),
+    ];
+
+    println!(
+        "{:<12}{:>8}{:>8}{:>8}{:>11}{:>8}",
+        "Strategy", "Frames", "Moves", "Score", "Destroyed", "Left"
+    );
+    for mut strategy in strategies {
+        let mut arcade = Arcade::new(program);
+        free_play
+            .apply(arcade.cpu_mut())
+            .expect("Could not apply the free play patch");
+        let report = arcade.play_with(strategy.as_mut());
+        println!(
+            "{:<12}{:>8}{:>8}{:>8}{:>11}{:>8}",
+            strategy.name(),
+            report.frames,
+            report.moves,
+            report.score,
+            report.destroyed,
+            report.blocks
+        );
     }
-    return v;
 }
 
 fn main() {
@@ -209,52 +162,73 @@ fn main() {
     let input = include_str!("../input");
 
     println!("Parsing input...");
-    let program = parse_input(&input);
+    let program = parse_input(input);
+
+    // `day13 play` for human control, `day13 play ai` to watch the AI,
+    // `day13 compare` to compare the joystick strategies,
+    // `day13 record [file.gif]` to record the AI playing.
+    // `--output=unicode|ascii|ansi` selects how the screen is drawn,
+    // `--patch=FILE` replaces the built-in free play patch set.
+    let mut output = Output::Unicode;
+    let mut free_play = include_str!("../free_play.patch").to_string();
+    let mut args = vec![];
+    for arg in env::args() {
+        if let Some(path) = arg.strip_prefix("--patch=") {
+            free_play = fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("Could not read {}: {}", path, e));
+        } else if let Some(target) = arg.strip_prefix("--output=") {
+            output = target.parse().unwrap_or_else(|e| panic!("{}", e));
+        } else {
+            args.push(arg);
+        }
+    }
+    let free_play =
+        PatchSet::parse(&free_play).unwrap_or_else(|e| panic!("Could not parse patch set: {}", e));
+    if args.get(1).map(|s| s.as_str()) == Some("record") {
+        let path = args.get(2).map(|s| s.as_str()).unwrap_or("arcade.gif");
+        record_game(&program, &free_play, path, 4);
+        return;
+    }
+    if args.get(1).map(|s| s.as_str()) == Some("compare") {
+        compare_strategies(&program, &free_play);
+        return;
+    }
+    if args.get(1).map(|s| s.as_str()) == Some("play") {
+        let human = args.get(2).map(|s| s.as_str()) != Some("ai");
+        let mut arcade = Arcade::new(&program);
+        free_play
+            .apply(arcade.cpu_mut())
+            .expect("Could not apply the free play patch");
+
+        let stop = play_interactive(&mut arcade, human, output);
+
+        if stop == Stop::Halt && arcade.blocks() == 0 {
+            println!("You win!");
+        } else if stop == Stop::Halt {
+            println!("Game over.");
+        } else if let Stop::InvalidTile { x, y, id } = stop {
+            println!("The game drew the unknown tile {} at ({}, {}).", id, x, y);
+        }
+        println!("Final Score: {}", arcade.display());
+        return;
+    }
 
     println!("\n--- Part 1: ---\n");
 
-    let (send_cpu, recv_cpu) = channel();
-    let (send_arcade, recv_arcade) = channel();
-    let cpu = Arc::new(Mutex::new(Processor::new(
-        0,
-        program.clone(),
-        recv_cpu,
-        send_arcade,
-    )));
-
-    let mut arcade = Arcade::new(recv_arcade, send_cpu);
-
-    let cpu_hdl = Arc::clone(&cpu);
-    thread::spawn(move || {
-        let mut cpu = cpu_hdl.lock().unwrap();
-        cpu.run();
-    });
+    let mut arcade = Arcade::new(&program);
+    arcade.run_frame();
 
-    arcade.init();
-
-    println!("{}", arcade.print_tiles());
-    println!(
-        "Number of Block Tiles: {}",
-        arcade.tiles().filter(|t| **t == Tile::Block).count()
-    );
+    println!("{}", arcade.render(output));
+    println!("Number of Block Tiles: {}", arcade.blocks());
 
     println!("\n--- Part 2: ---\n");
 
-    {
-        let mut cpu = cpu.lock().unwrap();
-        cpu.reset();
-        cpu.load_into_memory(&program);
-        cpu.set_address(0, 2);
-    }
-
-    let cpu_hdl = Arc::clone(&cpu);
-    thread::spawn(move || {
-        let mut cpu = cpu_hdl.lock().unwrap();
-        cpu.run();
-    });
-
-    arcade.reset();
+    let mut arcade = Arcade::new(&program);
+    free_play
+        .apply(arcade.cpu_mut())
+        .expect("Could not apply the free play patch");
     arcade.play();
 
+    println!("Frames played: {}", arcade.frames());
     println!("Final Score: {}", arcade.display());
 }
@@ -0,0 +1,267 @@
+// Property based fuzzing of the Intcode processor.
+//
+// Random memory images and inputs are run through `Processor` and through a
+// small reference interpreter below. Both have to agree on the produced output,
+// the final memory and the reason they stopped. A panic in `Processor` fails
+// the property as well.
+
+use day13::icm::{Processor, Stop};
+use proptest::prelude::*;
+use std::collections::HashMap;
+use std::sync::mpsc::channel;
+
+const MEMORY_LIMIT: usize = 256;
+const MAX_STEPS: usize = 1000;
+
+struct Outcome {
+    output: Vec<i32>,
+    memory: Vec<i32>,
+    stop: Option<Stop>,
+}
+
+fn run_processor(program: &[i32], input: &[i32]) -> Outcome {
+    let (send_cpu, recv_cpu) = channel();
+    let (send_out, recv_out) = channel();
+    for i in input {
+        send_cpu.send(*i).unwrap();
+    }
+    drop(send_cpu);
+
+    let mut cpu = Processor::new(0, program.to_vec(), recv_cpu, send_out);
+    cpu.set_memory_limit(MEMORY_LIMIT);
+    let mut stop = None;
+    for _ in 0..MAX_STEPS {
+        if let Err(s) = cpu.step() {
+            stop = Some(s);
+            break;
+        }
+    }
+
+    Outcome {
+        output: recv_out.try_iter().collect(),
+        memory: (0..MEMORY_LIMIT).map(|i| cpu.get_address(i)).collect(),
+        stop,
+    }
+}
+
+// Straightforward second implementation: sparse memory and i64 arithmetic,
+// results are range checked against i32 afterwards.
+fn run_reference(program: &[i32], input: &[i32]) -> Outcome {
+    let mut mem: HashMap<i64, i64> = program
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i as i64, i64::from(*v)))
+        .collect();
+    let mut input = input.iter();
+    let mut output = vec![];
+    let mut ip: i64 = 0;
+    let mut base: i64 = 0;
+
+    let stop = (|| {
+        for _ in 0..MAX_STEPS {
+            let at = ip as usize;
+            let val = *mem.get(&ip).unwrap_or(&0);
+            let opcode = val % 100;
+            let nparams = match opcode {
+                1 | 2 | 7 | 8 => 3,
+                3 | 4 | 9 => 1,
+                5 | 6 => 2,
+                99 => return Some(Stop::Halt),
+                _ => {
+                    return Some(Stop::InvalidOpcode {
+                        ip: at,
+                        opcode: val as i32,
+                    })
+                }
+            };
+            let invalid_mode = Stop::InvalidMode {
+                ip: at,
+                opcode: val as i32,
+            };
+            let mut modes = vec![];
+            let mut pcode = val / 100;
+            for _ in 0..nparams {
+                if pcode % 10 > 2 {
+                    return Some(invalid_mode);
+                }
+                modes.push(pcode % 10);
+                pcode /= 10;
+            }
+            if pcode != 0 {
+                return Some(invalid_mode);
+            }
+
+            let raw = |n: usize| *mem.get(&(ip + 1 + n as i64)).unwrap_or(&0);
+            let check = |addr: i64| {
+                if addr < 0 || addr >= MEMORY_LIMIT as i64 {
+                    Err(Stop::InvalidAddress { ip: at, addr })
+                } else {
+                    Ok(addr)
+                }
+            };
+            let addr = |n: usize| match modes[n] {
+                0 => check(raw(n)).map(Some),
+                2 => check(base + raw(n)).map(Some),
+                _ => Ok(None),
+            };
+            let read = |n: usize| -> Result<i64, Stop> {
+                match addr(n)? {
+                    Some(a) => Ok(*mem.get(&a).unwrap_or(&0)),
+                    None => Ok(raw(n)),
+                }
+            };
+            let write_addr = |n: usize| -> Result<i64, Stop> { addr(n)?.ok_or(invalid_mode) };
+            let fits = |v: i64| v >= i64::from(i32::MIN) && v <= i64::from(i32::MAX);
+
+            match opcode {
+                1 | 2 | 7 | 8 => {
+                    let a = match read(0) {
+                        Ok(v) => v,
+                        Err(s) => return Some(s),
+                    };
+                    let b = match read(1) {
+                        Ok(v) => v,
+                        Err(s) => return Some(s),
+                    };
+                    let dst = match write_addr(2) {
+                        Ok(v) => v,
+                        Err(s) => return Some(s),
+                    };
+                    let res = match opcode {
+                        1 => a + b,
+                        2 => a * b,
+                        7 => (a < b) as i64,
+                        _ => (a == b) as i64,
+                    };
+                    if !fits(res) {
+                        return Some(Stop::Overflow { ip: at });
+                    }
+                    mem.insert(dst, res);
+                    ip += 4;
+                }
+                3 => {
+                    let dst = match write_addr(0) {
+                        Ok(v) => v,
+                        Err(s) => return Some(s),
+                    };
+                    match input.next() {
+                        Some(v) => mem.insert(dst, i64::from(*v)),
+                        None => return Some(Stop::InputClosed),
+                    };
+                    ip += 2;
+                }
+                4 => {
+                    match read(0) {
+                        Ok(v) => output.push(v as i32),
+                        Err(s) => return Some(s),
+                    };
+                    ip += 2;
+                }
+                5 | 6 => {
+                    let cond = match read(0) {
+                        Ok(v) => v != 0,
+                        Err(s) => return Some(s),
+                    };
+                    if cond == (opcode == 5) {
+                        ip = match read(1).and_then(check) {
+                            Ok(v) => v,
+                            Err(s) => return Some(s),
+                        };
+                    } else {
+                        ip += 3;
+                    }
+                }
+                _ => {
+                    let off = match read(0) {
+                        Ok(v) => v,
+                        Err(s) => return Some(s),
+                    };
+                    if !fits(base + off) {
+                        return Some(Stop::Overflow { ip: at });
+                    }
+                    base += off;
+                    ip += 2;
+                }
+            }
+        }
+        None
+    })();
+
+    Outcome {
+        output,
+        memory: (0..MEMORY_LIMIT as i64)
+            .map(|i| *mem.get(&i).unwrap_or(&0) as i32)
+            .collect(),
+        stop,
+    }
+}
+
+// Memory cells biased towards valid instructions and small addresses.
+fn cell() -> impl Strategy<Value = i32> {
+    prop_oneof![
+        4 => (1..=9, 0..3, 0..3, 0..3).prop_map(|(op, a, b, c)| op + a * 100 + b * 1000 + c * 10000),
+        1 => Just(99),
+        4 => -5..64,
+        1 => any::<i32>(),
+    ]
+}
+
+fn program() -> impl Strategy<Value = Vec<i32>> {
+    prop::collection::vec(cell(), 1..64)
+}
+
+fn input() -> impl Strategy<Value = Vec<i32>> {
+    prop::collection::vec(prop_oneof![-100..100, any::<i32>()], 0..8)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn processor_matches_reference(program in program(), input in input()) {
+        let cpu = run_processor(&program, &input);
+        let reference = run_reference(&program, &input);
+
+        prop_assert_eq!(cpu.stop, reference.stop);
+        prop_assert_eq!(cpu.output, reference.output);
+        prop_assert_eq!(cpu.memory, reference.memory);
+    }
+
+    #[test]
+    fn processor_never_panics(program in prop::collection::vec(any::<i32>(), 0..32),
+                              input in input()) {
+        // unbiased garbage, only checks that nothing panics
+        run_processor(&program, &input);
+    }
+
+    #[test]
+    fn step_back_restores_initial_state(program in program(), input in input()) {
+        let (send_cpu, recv_cpu) = channel();
+        let (send_out, _recv_out) = channel();
+        for i in &input {
+            send_cpu.send(*i).unwrap();
+        }
+        drop(send_cpu);
+
+        let mut cpu = Processor::new(0, program.clone(), recv_cpu, send_out);
+        cpu.set_memory_limit(MEMORY_LIMIT);
+        cpu.enable_history();
+        for _ in 0..MAX_STEPS {
+            if cpu.step().is_err() {
+                break;
+            }
+        }
+        let executed = cpu.history().len();
+
+        let mut undone = 0;
+        while cpu.step_back().is_some() {
+            undone += 1;
+        }
+        prop_assert_eq!(undone, executed);
+        prop_assert_eq!(cpu.ip(), 0);
+        prop_assert_eq!(cpu.rel_base(), 0);
+        for (i, v) in program.iter().enumerate() {
+            prop_assert_eq!(cpu.get_address(i), *v);
+        }
+    }
+}
@@ -0,0 +1,1029 @@
+pub mod reaction {
+    use itertools::Itertools;
+    use std::collections::{HashMap, HashSet};
+    use std::fmt;
+
+    #[derive(Hash, PartialEq, Eq, Clone, Debug)]
+    pub struct Ingredient {
+        pub name: String,
+        pub amount: u64,
+    }
+
+    impl Ingredient {
+        pub fn new() -> Ingredient {
+            Ingredient {
+                name: "NONE".to_string(),
+                amount: 0,
+            }
+        }
+    }
+
+    impl Default for Ingredient {
+        fn default() -> Ingredient {
+            Ingredient::new()
+        }
+    }
+
+    // Maps the product of every reaction to its reactants.
+    pub type Recipe = HashMap<Ingredient, Vec<Ingredient>>;
+
+    // A list like `7 A, 1 B`, also used for stocks of raw materials.
+    pub fn parse_ingredients(s: &str) -> Vec<Ingredient> {
+        let mut v = vec![];
+        for (amount, name) in s.split_whitespace().tuples() {
+            let amount = amount.parse::<u64>().expect("Couldn't parse amount.");
+            v.push(Ingredient {
+                name: name.trim_end_matches(',').to_string(),
+                amount,
+            });
+        }
+        v
+    }
+
+    pub fn parse_input(s: &str) -> Recipe {
+        let mut map = HashMap::new();
+
+        for line in s.lines() {
+            let parts = line.split(" => ").collect::<Vec<_>>();
+            let ops = parts[0];
+            let prod = parts[1];
+
+            let product = parse_ingredients(prod).pop().unwrap_or_default();
+            map.insert(product, parse_ingredients(ops));
+        }
+
+        map
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Error {
+        // The chemicals produce each other, the first and last one are the same.
+        Cycle(Vec<String>),
+        // The chemical is neither raw nor produced by any reaction.
+        Unreachable(String),
+        // The reaction producing the chemical yields nothing.
+        EmptyReaction(String),
+        // The amounts do not fit an u64.
+        Overflow,
+        // The budget is given in a chemical that is not raw.
+        NotRaw(String),
+        // The target can be produced without the chemical of the budget.
+        Unbounded(String),
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Error::Cycle(chain) => write!(f, "reactions form a cycle: {}", chain.join(" <- ")),
+                Error::Unreachable(name) => write!(f, "no reaction produces {}", name),
+                Error::EmptyReaction(name) => write!(f, "the reaction for {} yields nothing", name),
+                Error::Overflow => write!(f, "amounts overflow"),
+                Error::NotRaw(name) => write!(f, "{} is not a raw material", name),
+                Error::Unbounded(name) => write!(f, "the target does not need any {}", name),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    // Everything needed to produce an amount of a chemical.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct Plan {
+        // How many times each reaction runs, by the name of its product.
+        pub runs: HashMap<String, u64>,
+        // The amounts of raw materials used.
+        pub raw: HashMap<String, u64>,
+        // What is produced but not used, by name.
+        pub leftover: HashMap<String, u64>,
+        // The amounts taken from a supply instead of being produced.
+        pub bought: HashMap<String, u64>,
+    }
+
+    impl Plan {
+        pub fn runs(&self, name: &str) -> u64 {
+            self.runs.get(name).copied().unwrap_or(0)
+        }
+
+        pub fn raw(&self, name: &str) -> u64 {
+            self.raw.get(name).copied().unwrap_or(0)
+        }
+
+        pub fn leftover(&self, name: &str) -> u64 {
+            self.leftover.get(name).copied().unwrap_or(0)
+        }
+
+        pub fn bought(&self, name: &str) -> u64 {
+            self.bought.get(name).copied().unwrap_or(0)
+        }
+    }
+
+    /*
+    The reactions by the name of their product. Raw materials are never
+    produced, even when a reaction for them exists.
+     */
+    #[derive(Debug, Clone)]
+    pub struct Reactions {
+        reactions: HashMap<String, Ingredient>,
+        reactants: HashMap<String, Vec<Ingredient>>,
+        raw: HashSet<String>,
+    }
+
+    impl Reactions {
+        pub fn new(recipe: &Recipe, raw: &[&str]) -> Reactions {
+            let mut reactions = HashMap::new();
+            let mut reactants = HashMap::new();
+            for (product, inputs) in recipe {
+                reactions.insert(product.name.clone(), product.clone());
+                reactants.insert(product.name.clone(), inputs.clone());
+            }
+            Reactions {
+                reactions,
+                reactants,
+                raw: raw.iter().map(|s| s.to_string()).collect(),
+            }
+        }
+
+        // All reactions except the ones for raw materials, sorted by product.
+        pub fn iter(&self) -> Vec<(&Ingredient, &[Ingredient])> {
+            let mut v = self
+                .reactions
+                .keys()
+                .filter_map(|name| self.get(name))
+                .collect::<Vec<_>>();
+            v.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+            v
+        }
+
+        pub fn is_raw(&self, name: &str) -> bool {
+            self.raw.contains(name)
+        }
+
+        // The product and reactants of the reaction producing the chemical.
+        pub fn get(&self, name: &str) -> Option<(&Ingredient, &[Ingredient])> {
+            match (self.reactions.get(name), self.reactants.get(name)) {
+                (Some(p), Some(r)) if !self.is_raw(name) => Some((p, r)),
+                _ => None,
+            }
+        }
+
+        /*
+        All chemicals involved in producing the target, every chemical before
+        the ones it is made of. Raw materials come last.
+         */
+        pub fn order(&self, target: &str) -> Result<Vec<String>, Error> {
+            let mut done = HashSet::new();
+            let mut path = vec![];
+            let mut post = vec![];
+            self.visit(target, &mut done, &mut path, &mut post)?;
+            let (mut raw, mut order): (Vec<_>, Vec<_>) =
+                post.into_iter().rev().partition(|n| self.is_raw(n));
+            order.append(&mut raw);
+            Ok(order)
+        }
+
+        fn visit(
+            &self,
+            name: &str,
+            done: &mut HashSet<String>,
+            path: &mut Vec<String>,
+            post: &mut Vec<String>,
+        ) -> Result<(), Error> {
+            if done.contains(name) {
+                return Ok(());
+            }
+            if let Some(i) = path.iter().position(|n| n == name) {
+                let mut cycle = path[i..].to_vec();
+                cycle.push(name.to_string());
+                return Err(Error::Cycle(cycle));
+            }
+            if !self.is_raw(name) {
+                let (product, inputs) = match self.get(name) {
+                    Some(r) => r,
+                    None => return Err(Error::Unreachable(name.to_string())),
+                };
+                if product.amount == 0 {
+                    return Err(Error::EmptyReaction(name.to_string()));
+                }
+                path.push(name.to_string());
+                for input in inputs {
+                    self.visit(&input.name, done, path, post)?;
+                }
+                path.pop();
+            }
+            done.insert(name.to_string());
+            post.push(name.to_string());
+            Ok(())
+        }
+
+        pub fn plan(&self, target: &str, amount: u64) -> Result<Plan, Error> {
+            let order = self.order(target)?;
+            self.plan_in(&order, target, amount)
+        }
+
+        /*
+        Plans the production along an order from `order`. Every chemical is
+        only resolved once all of its consumers are, so its reaction runs just
+        often enough with whole runs.
+         */
+        pub fn plan_in(&self, order: &[String], target: &str, amount: u64) -> Result<Plan, Error> {
+            self.plan_supplied(order, target, amount, &HashMap::new())
+        }
+
+        /*
+        Like `plan_in`, but the chemicals in the supply are taken from it as
+        far as it goes before their reactions run.
+         */
+        pub fn plan_supplied(
+            &self,
+            order: &[String],
+            target: &str,
+            amount: u64,
+            supply: &HashMap<String, u64>,
+        ) -> Result<Plan, Error> {
+            let mut need = HashMap::new();
+            need.insert(target.to_string(), amount);
+            let mut plan = Plan::default();
+            for name in order {
+                let mut wanted = need.remove(name).unwrap_or(0);
+                if self.is_raw(name) {
+                    plan.raw.insert(name.clone(), wanted);
+                    continue;
+                }
+                if let Some(&available) = supply.get(name) {
+                    let used = available.min(wanted);
+                    if used > 0 {
+                        plan.bought.insert(name.clone(), used);
+                    }
+                    wanted -= used;
+                }
+                let (product, inputs) = self
+                    .get(name)
+                    .ok_or_else(|| Error::Unreachable(name.clone()))?;
+                let runs = wanted / product.amount + (wanted % product.amount != 0) as u64;
+                // the surplus is short of the next multiple, even if that overflows
+                let surplus = (product.amount - wanted % product.amount) % product.amount;
+                plan.runs.insert(name.clone(), runs);
+                if surplus > 0 {
+                    plan.leftover.insert(name.clone(), surplus);
+                }
+                for input in inputs {
+                    let add = input.amount.checked_mul(runs).ok_or(Error::Overflow)?;
+                    let n = need.entry(input.name.clone()).or_insert(0);
+                    *n = n.checked_add(add).ok_or(Error::Overflow)?;
+                }
+            }
+            Ok(plan)
+        }
+
+        // A copy that takes the given chemicals as raw instead.
+        pub fn with_raw(&self, raw: &[&str]) -> Reactions {
+            Reactions {
+                reactions: self.reactions.clone(),
+                reactants: self.reactants.clone(),
+                raw: raw.iter().map(|s| s.to_string()).collect(),
+            }
+        }
+
+        // The raw materials, sorted.
+        pub fn raw(&self) -> Vec<&str> {
+            let mut v = self.raw.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+            v.sort();
+            v
+        }
+
+        fn affordable(
+            &self,
+            order: &[String],
+            target: &str,
+            n: u64,
+            stock: &[Ingredient],
+        ) -> Result<bool, Error> {
+            match self.plan_in(order, target, n) {
+                Ok(plan) => Ok(stock.iter().all(|s| plan.raw(&s.name) <= s.amount)),
+                Err(Error::Overflow) => Ok(false),
+                Err(e) => Err(e),
+            }
+        }
+
+        // The most of the target that can be produced from the budget of a raw material.
+        pub fn max_producible(&self, target: &str, budget: &Ingredient) -> Result<u64, Error> {
+            self.max_producible_from(target, std::slice::from_ref(budget))
+        }
+
+        /*
+        The most of the target that can be produced from the stocks of raw
+        materials, raw materials without a stock are not limited. The amount
+        needed only grows with the amount produced, so the limit is bracketed
+        by doubling and then found by bisection.
+         */
+        pub fn max_producible_from(
+            &self,
+            target: &str,
+            stock: &[Ingredient],
+        ) -> Result<u64, Error> {
+            if let Some(s) = stock.iter().find(|s| !self.is_raw(&s.name)) {
+                return Err(Error::NotRaw(s.name.clone()));
+            }
+            let order = self.order(target)?;
+            if !stock.iter().any(|s| order.contains(&s.name)) {
+                let names = stock.iter().map(|s| s.name.as_str()).collect::<Vec<_>>();
+                return Err(Error::Unbounded(names.join(", ")));
+            }
+            if !self.affordable(&order, target, 1, stock)? {
+                return Ok(0);
+            }
+            // lo can be produced, hi can not
+            let mut lo: u64 = 1;
+            let mut hi = loop {
+                match lo.checked_mul(2) {
+                    Some(n) if self.affordable(&order, target, n, stock)? => lo = n,
+                    Some(n) => break n,
+                    None if self.affordable(&order, target, u64::MAX, stock)? => {
+                        return Ok(u64::MAX)
+                    }
+                    None => break u64::MAX,
+                }
+            };
+            while hi - lo > 1 {
+                let mid = lo + (hi - lo) / 2;
+                if self.affordable(&order, target, mid, stock)? {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            Ok(lo)
+        }
+    }
+}
+
+/*
+Queries for understanding a reaction list. Costs are given in one raw
+material, usually ORE.
+ */
+pub mod analysis {
+    use crate::reaction::{Error, Reactions};
+    use std::collections::HashMap;
+
+    // The ORE needed for a single unit of the chemical, None if it can not be produced.
+    pub fn unit_cost(reactions: &Reactions, name: &str, raw: &str) -> Option<u64> {
+        reactions.plan(name, 1).ok().map(|p| p.raw(raw))
+    }
+
+    /*
+    The reactions as a Graphviz graph. Edges go from the reactants to the
+    product and are labeled with the amount used per run, nodes with the
+    amount a run yields and the cost of one unit.
+     */
+    pub fn to_dot(reactions: &Reactions, raw: &str) -> String {
+        let mut s = String::from("digraph reactions {\n    rankdir=LR;\n");
+        let mut raws = vec![];
+        for (product, inputs) in reactions.iter() {
+            let cost = match unit_cost(reactions, &product.name, raw) {
+                Some(c) => format!("\\n{} {}", c, raw),
+                None => String::new(),
+            };
+            s.push_str(&format!(
+                "    \"{}\" [label=\"{}\\n{} per run{}\"];\n",
+                product.name, product.name, product.amount, cost
+            ));
+            for input in inputs {
+                if reactions.is_raw(&input.name) && !raws.contains(&input.name) {
+                    raws.push(input.name.clone());
+                }
+                s.push_str(&format!(
+                    "    \"{}\" -> \"{}\" [label=\"{}\", weight={}];\n",
+                    input.name, product.name, input.amount, input.amount
+                ));
+            }
+        }
+        raws.sort();
+        for name in raws {
+            s.push_str(&format!(
+                "    \"{}\" [label=\"{}\\nraw\", shape=box];\n",
+                name, name
+            ));
+        }
+        s.push_str("}\n");
+        s
+    }
+
+    /*
+    The longest chain of reactions leading to the target, from the target
+    down to a raw material. Its length minus one is the number of reactions
+    that have to run one after the other.
+     */
+    pub fn critical_path(reactions: &Reactions, target: &str) -> Result<Vec<String>, Error> {
+        let order = reactions.order(target)?;
+        // longest path down to a raw material, resolved from the raw end,
+        // ties go to the reactant listed first
+        let mut longest: HashMap<&str, Vec<String>> = HashMap::new();
+        for name in order.iter().rev() {
+            let mut path = match reactions.get(name) {
+                Some((_, inputs)) => inputs
+                    .iter()
+                    .rev()
+                    .map(|i| &longest[i.name.as_str()])
+                    .max_by_key(|p| p.len())
+                    .cloned()
+                    .unwrap_or_default(),
+                None => vec![],
+            };
+            path.insert(0, name.clone());
+            longest.insert(name, path);
+        }
+        Ok(longest.remove(target).unwrap_or_default())
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Waste {
+        pub name: String,
+        // Units produced but not used.
+        pub amount: u64,
+        // The raw material spent on them, rounded down.
+        pub cost: u64,
+    }
+
+    // The leftovers of producing the target, the most expensive first.
+    pub fn waste(
+        reactions: &Reactions,
+        target: &str,
+        amount: u64,
+        raw: &str,
+    ) -> Result<Vec<Waste>, Error> {
+        let plan = reactions.plan(target, amount)?;
+        let mut v = vec![];
+        for (name, &left) in &plan.leftover {
+            let (product, _) = reactions
+                .get(name)
+                .ok_or_else(|| Error::Unreachable(name.clone()))?;
+            // a whole run costs exactly this much, the waste its share of it
+            let run = reactions.plan(name, product.amount)?.raw(raw);
+            v.push(Waste {
+                name: name.clone(),
+                amount: left,
+                cost: (u128::from(run) * u128::from(left) / u128::from(product.amount)) as u64,
+            });
+        }
+        v.sort_by(|a, b| b.cost.cmp(&a.cost).then_with(|| a.name.cmp(&b.name)));
+        Ok(v)
+    }
+
+    // The additional raw material needed for one more unit of the target.
+    pub fn marginal_cost(
+        reactions: &Reactions,
+        target: &str,
+        amount: u64,
+        raw: &str,
+    ) -> Result<u64, Error> {
+        let order = reactions.order(target)?;
+        let now = reactions.plan_in(&order, target, amount)?.raw(raw);
+        let next = amount.checked_add(1).ok_or(Error::Overflow)?;
+        let then = reactions.plan_in(&order, target, next)?.raw(raw);
+        Ok(then - now)
+    }
+}
+
+/*
+Buying chemicals instead of producing them. Every offered chemical has a
+stock and a price per unit, raw materials have to be bought, everything else
+offered may be.
+ */
+pub mod market {
+    use crate::reaction::{Error, Plan, Reactions};
+    use std::collections::HashMap;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Offer {
+        pub name: String,
+        pub stock: u64,
+        pub price: u64,
+    }
+
+    impl Offer {
+        pub fn new(name: &str, stock: u64, price: u64) -> Offer {
+            Offer {
+                name: name.to_string(),
+                stock,
+                price,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Mix {
+        // What is bought, by name.
+        pub bought: HashMap<String, u64>,
+        pub cost: u64,
+        // The production, with the bought chemicals in its supply.
+        pub plan: Plan,
+    }
+
+    // The price of buying everything the plan needs, None if an offer does not suffice.
+    fn price(plan: &Plan, offers: &[Offer]) -> Option<u64> {
+        let mut cost: u64 = 0;
+        for (name, &amount) in plan.raw.iter().chain(plan.bought.iter()) {
+            if amount == 0 {
+                continue;
+            }
+            let offer = offers.iter().find(|o| &o.name == name)?;
+            if amount > offer.stock {
+                return None;
+            }
+            cost = cost.checked_add(amount.checked_mul(offer.price)?)?;
+        }
+        Some(cost)
+    }
+
+    // The bound for n units, nothing is needed for none even if they can not be had.
+    fn times(n: u64, bound: f64) -> f64 {
+        if n == 0 {
+            0.0
+        } else {
+            n as f64 * bound
+        }
+    }
+
+    // A chemical of the order with its offer and reaction, inputs by their index.
+    struct Node {
+        offer: Option<(u64, u64)>,
+        reaction: Option<(u64, Vec<(usize, u64)>)>,
+        // The lowest price per unit, ignoring stocks and whole runs.
+        unit: f64,
+    }
+
+    fn nodes(reactions: &Reactions, order: &[String], offers: &[Offer]) -> Vec<Node> {
+        let index = |name: &str| order.iter().position(|n| n == name).unwrap();
+        let mut nodes = order
+            .iter()
+            .map(|name| {
+                let offer = offers.iter().find(|o| &o.name == name);
+                Node {
+                    offer: offer.map(|o| (o.price, o.stock)),
+                    reaction: reactions.get(name).map(|(product, inputs)| {
+                        let inputs = inputs
+                            .iter()
+                            .map(|input| (index(&input.name), input.amount))
+                            .collect();
+                        (product.amount, inputs)
+                    }),
+                    unit: match offer {
+                        Some(o) if o.stock > 0 => o.price as f64,
+                        _ => f64::INFINITY,
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+        // inputs come after their consumers
+        for i in (0..nodes.len()).rev() {
+            if let Some((per_run, inputs)) = &nodes[i].reaction {
+                let produced = inputs
+                    .iter()
+                    .map(|&(j, n)| times(n, nodes[j].unit))
+                    .sum::<f64>()
+                    / *per_run as f64;
+                nodes[i].unit = nodes[i].unit.min(produced);
+            }
+        }
+        nodes
+    }
+
+    /*
+    A lower bound for the cost of the chemicals from `i` on. Needs only grow,
+    so every chemical has to run its reaction at least often enough to cover
+    what the stock does not, and the raw materials these runs need have to be
+    bought. The rest of each need costs at least its unit price, less what
+    the surplus of the forced runs is worth. The plain unit prices of the
+    needs are a bound as well, the larger one is taken.
+     */
+    fn bound(nodes: &[Node], i: usize, need: &[u64]) -> f64 {
+        let plain = (i..nodes.len())
+            .map(|j| times(need[j], nodes[j].unit))
+            .sum::<f64>();
+        let mut forced = need.to_vec();
+        let mut raw = 0.0;
+        let mut rest = 0.0;
+        for j in i..nodes.len() {
+            let wanted = forced[j];
+            let (price, stock) = nodes[j].offer.unwrap_or((0, 0));
+            match &nodes[j].reaction {
+                None if wanted > stock => return f64::INFINITY,
+                None => raw += times(wanted, price as f64),
+                Some((per_run, inputs)) => {
+                    let runs = (wanted - stock.min(wanted)).div_ceil(*per_run);
+                    let made = runs.saturating_mul(*per_run);
+                    rest += times(wanted.saturating_sub(made), nodes[j].unit);
+                    rest -= times(made.saturating_sub(wanted), nodes[j].unit);
+                    for &(k, n) in inputs {
+                        forced[k] = forced[k].saturating_add(n.saturating_mul(runs));
+                    }
+                }
+            }
+        }
+        plain.max(raw + rest.max(0.0))
+    }
+
+    struct Search<'a> {
+        nodes: &'a [Node],
+        // The cheapest cost found so far and what is bought for it, by index.
+        best: Option<(u64, Vec<u64>)>,
+    }
+
+    impl Search<'_> {
+        // Decides how much of the chemical at `i` to buy, all earlier ones are decided.
+        fn visit(&mut self, i: usize, need: &[u64], cost: u64, bought: &mut Vec<u64>) {
+            let nodes = self.nodes;
+            if i == nodes.len() {
+                if self.best.as_ref().is_none_or(|(b, _)| cost < *b) {
+                    self.best = Some((cost, bought.clone()));
+                }
+                return;
+            }
+            let wanted = need[i];
+            let (price, stock) = nodes[i].offer.unwrap_or((0, 0));
+            let (per_run, inputs) = match &nodes[i].reaction {
+                Some((per_run, inputs)) => (*per_run, &inputs[..]),
+                // raw materials are bought completely
+                None if wanted <= stock => (1, &[][..]),
+                None => return,
+            };
+
+            // every number of runs from covering all but the stock to covering everything
+            let (least, most) = if nodes[i].reaction.is_some() {
+                let rest = wanted - stock.min(wanted);
+                (rest.div_ceil(per_run), wanted.div_ceil(per_run))
+            } else {
+                (0, 0)
+            };
+            let mut choices = vec![];
+            for runs in least..=most {
+                let buy = wanted.saturating_sub(runs.saturating_mul(per_run));
+                let cost = match buy.checked_mul(price).and_then(|c| c.checked_add(cost)) {
+                    Some(c) => c,
+                    None => continue,
+                };
+                let mut next = need.to_vec();
+                let fits = inputs.iter().all(|&(j, n)| {
+                    match n.checked_mul(runs).and_then(|n| n.checked_add(next[j])) {
+                        Some(total) => {
+                            next[j] = total;
+                            true
+                        }
+                        None => false,
+                    }
+                });
+                if fits {
+                    let lower = cost as f64 + bound(nodes, i + 1, &next);
+                    choices.push((lower, buy, cost, next));
+                }
+            }
+            choices.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+            for (lower, buy, cost, next) in choices {
+                // costs are whole numbers, so the bound has to beat the best by at least one
+                if let Some((best, _)) = &self.best {
+                    if lower >= *best as f64 - 0.5 {
+                        break;
+                    }
+                }
+                bought[i] = buy;
+                self.visit(i + 1, &next, cost, bought);
+            }
+            bought[i] = 0;
+        }
+    }
+
+    /*
+    The cheapest way to produce the amount of the target. The chemicals are
+    decided along the order of the reactions, once everything they are used
+    for is known: how many runs of their reaction to do and how much to buy
+    instead, at most their stock. Raw materials are bought. Choices with the
+    lowest bound are tried first, and a branch is dropped once its cost plus
+    the bound for the rest can not beat the best mix found. Returns None if
+    the offers do not suffice.
+     */
+    pub fn cheapest(
+        reactions: &Reactions,
+        target: &str,
+        amount: u64,
+        offers: &[Offer],
+    ) -> Result<Option<Mix>, Error> {
+        let order = reactions.order(target)?;
+        let nodes = nodes(reactions, &order, offers);
+        let mut search = Search {
+            nodes: &nodes,
+            best: None,
+        };
+        let mut need = vec![0; order.len()];
+        need[0] = amount;
+        search.visit(0, &need, 0, &mut vec![0; order.len()]);
+        let (cost, bought) = match search.best {
+            Some(best) => best,
+            None => return Ok(None),
+        };
+        let supply = order
+            .iter()
+            .zip(&bought)
+            .filter(|(name, &n)| n > 0 && !reactions.is_raw(name))
+            .map(|(name, &n)| (name.clone(), n))
+            .collect();
+        let plan = reactions.plan_supplied(&order, target, amount, &supply)?;
+        debug_assert_eq!(price(&plan, offers), Some(cost));
+        let bought = plan
+            .raw
+            .iter()
+            .chain(plan.bought.iter())
+            .filter(|(_, &n)| n > 0)
+            .map(|(k, &n)| (k.clone(), n))
+            .collect();
+        Ok(Some(Mix { bought, cost, plan }))
+    }
+}
+
+#[test]
+fn test_plan() {
+    use reaction::{parse_input, Error, Reactions};
+
+    let recipe = parse_input(
+        r"10 ORE => 10 A
+1 ORE => 1 B
+7 A, 1 B => 1 C
+7 A, 1 C => 1 D
+7 A, 1 D => 1 E
+7 A, 1 E => 1 FUEL",
+    );
+    let reactions = Reactions::new(&recipe, &["ORE"]);
+    let order = reactions.order("FUEL").unwrap();
+    assert_eq!(order[0], "FUEL");
+    assert_eq!(order.last().unwrap(), "ORE");
+
+    let plan = reactions.plan("FUEL", 1).unwrap();
+    assert_eq!(plan.raw("ORE"), 31);
+    assert_eq!(plan.runs("A"), 3);
+    assert_eq!(plan.runs("B"), 1);
+    assert_eq!(plan.runs("E"), 1);
+    assert_eq!(plan.leftover("A"), 2);
+    assert_eq!(plan.leftover("B"), 0);
+
+    let plan = reactions.plan("FUEL", 10).unwrap();
+    assert_eq!(plan.runs("A"), 28);
+    assert_eq!(plan.raw("ORE"), 290);
+    assert!(plan.leftover.is_empty());
+
+    // D can be taken from the raw materials instead
+    let reactions = Reactions::new(&recipe, &["ORE", "D"]);
+    let plan = reactions.plan("FUEL", 1).unwrap();
+    assert_eq!(plan.raw("D"), 1);
+    assert_eq!(plan.raw("ORE"), 20);
+    assert_eq!(plan.runs("C"), 0);
+
+    let big = parse_input("1000000000000 ORE => 1 FUEL");
+    let reactions = Reactions::new(&big, &["ORE"]);
+    assert_eq!(reactions.plan("FUEL", 100_000_000), Err(Error::Overflow));
+
+    let cyclic = parse_input("1 ORE, 1 B => 1 A\n2 A => 1 B\n1 B => 1 FUEL");
+    let reactions = Reactions::new(&cyclic, &["ORE"]);
+    let err = reactions.plan("FUEL", 1).unwrap_err();
+    assert_eq!(err, Error::Cycle(vec!["B".into(), "A".into(), "B".into()]));
+    assert_eq!(err.to_string(), "reactions form a cycle: B <- A <- B");
+
+    let missing = parse_input("1 ORE, 2 X => 1 FUEL");
+    let reactions = Reactions::new(&missing, &["ORE"]);
+    assert_eq!(
+        reactions.plan("FUEL", 1),
+        Err(Error::Unreachable("X".into()))
+    );
+    assert_eq!(
+        reactions.plan("GOLD", 1),
+        Err(Error::Unreachable("GOLD".into()))
+    );
+}
+
+#[test]
+fn test_max_producible() {
+    use reaction::{parse_input, Error, Ingredient, Reactions};
+
+    let ore = |amount| Ingredient {
+        name: "ORE".to_string(),
+        amount,
+    };
+    let recipe = parse_input(
+        r"10 ORE => 10 A
+1 ORE => 1 B
+7 A, 1 B => 1 C
+7 A, 1 C => 1 D
+7 A, 1 D => 1 E
+7 A, 1 E => 1 FUEL",
+    );
+    let reactions = Reactions::new(&recipe, &["ORE"]);
+    assert_eq!(reactions.max_producible("FUEL", &ore(30)), Ok(0));
+    assert_eq!(reactions.max_producible("FUEL", &ore(31)), Ok(1));
+    assert_eq!(reactions.max_producible("FUEL", &ore(290)), Ok(10));
+    assert_eq!(reactions.max_producible("FUEL", &ore(289)), Ok(9));
+    assert_eq!(reactions.max_producible("A", &ore(25)), Ok(20));
+    assert_eq!(reactions.max_producible("C", &ore(0)), Ok(0));
+
+    // any raw material can be the limit
+    let reactions = Reactions::new(&recipe, &["ORE", "B"]);
+    let b = Ingredient {
+        name: "B".to_string(),
+        amount: 3,
+    };
+    assert_eq!(reactions.max_producible("FUEL", &b), Ok(3));
+    assert_eq!(
+        reactions.max_producible("A", &b),
+        Err(Error::Unbounded("B".into()))
+    );
+    assert_eq!(
+        reactions.max_producible(
+            "FUEL",
+            &Ingredient {
+                name: "C".into(),
+                amount: 1
+            }
+        ),
+        Err(Error::NotRaw("C".into()))
+    );
+
+    let cheap = Reactions::new(&parse_input("1 ORE => 2 FUEL"), &["ORE"]);
+    assert_eq!(cheap.max_producible("FUEL", &ore(u64::MAX)), Ok(u64::MAX));
+    assert_eq!(
+        cheap.max_producible("FUEL", &ore(u64::MAX / 2)),
+        Ok(u64::MAX - 1)
+    );
+}
+
+#[test]
+fn test_analysis() {
+    use analysis::{critical_path, marginal_cost, to_dot, unit_cost, waste, Waste};
+    use reaction::{parse_input, Reactions};
+
+    let recipe = parse_input(
+        r"10 ORE => 10 A
+1 ORE => 1 B
+7 A, 1 B => 1 C
+7 A, 1 C => 1 D
+7 A, 1 D => 1 E
+7 A, 1 E => 1 FUEL",
+    );
+    let reactions = Reactions::new(&recipe, &["ORE"]);
+
+    assert_eq!(unit_cost(&reactions, "A", "ORE"), Some(10));
+    assert_eq!(unit_cost(&reactions, "C", "ORE"), Some(11));
+    assert_eq!(unit_cost(&reactions, "X", "ORE"), None);
+
+    let dot = to_dot(&reactions, "ORE");
+    assert!(dot.starts_with("digraph reactions {\n"));
+    assert!(dot.contains("    \"A\" [label=\"A\\n10 per run\\n10 ORE\"];\n"));
+    assert!(dot.contains("    \"FUEL\" [label=\"FUEL\\n1 per run\\n31 ORE\"];\n"));
+    assert!(dot.contains("    \"A\" -> \"FUEL\" [label=\"7\", weight=7];\n"));
+    assert!(dot.contains("    \"ORE\" [label=\"ORE\\nraw\", shape=box];\n"));
+    assert!(dot.ends_with("}\n"));
+
+    assert_eq!(
+        critical_path(&reactions, "FUEL").unwrap(),
+        vec!["FUEL", "E", "D", "C", "A", "ORE"]
+    );
+
+    assert_eq!(
+        waste(&reactions, "FUEL", 1, "ORE").unwrap(),
+        vec![Waste {
+            name: "A".to_string(),
+            amount: 2,
+            cost: 2,
+        }]
+    );
+    assert!(waste(&reactions, "FUEL", 10, "ORE").unwrap().is_empty());
+
+    // the leftover A covers part of the next FUEL
+    assert_eq!(marginal_cost(&reactions, "FUEL", 4, "ORE"), Ok(21));
+    assert_eq!(marginal_cost(&reactions, "FUEL", 1, "ORE"), Ok(31));
+    assert_eq!(marginal_cost(&reactions, "FUEL", 0, "ORE"), Ok(31));
+}
+
+#[test]
+fn test_market() {
+    use market::{cheapest, Offer};
+    use reaction::{parse_ingredients, parse_input, Error, Reactions};
+
+    let recipe = parse_input(
+        r"10 ORE => 10 A
+1 IRON => 1 B
+7 A, 1 B => 1 C
+7 A, 1 C => 1 D
+7 A, 1 D => 1 E
+7 A, 1 E => 1 FUEL",
+    );
+    let reactions = Reactions::new(&recipe, &["ORE", "IRON"]);
+
+    // two fuel need 60 ORE and 2 IRON, three 90 ORE and 3 IRON
+    let stock = parse_ingredients("100 ORE, 2 IRON");
+    assert_eq!(reactions.max_producible_from("FUEL", &stock), Ok(2));
+    let stock = parse_ingredients("100 ORE, 10 IRON");
+    assert_eq!(reactions.max_producible_from("FUEL", &stock), Ok(3));
+    let stock = parse_ingredients("10 IRON");
+    assert_eq!(reactions.max_producible_from("FUEL", &stock), Ok(10));
+    assert_eq!(
+        reactions.max_producible_from("A", &stock),
+        Err(Error::Unbounded("IRON".into()))
+    );
+
+    let offers = vec![
+        Offer::new("ORE", 1000, 1),
+        Offer::new("IRON", 10, 2),
+        Offer::new("D", 5, 15),
+    ];
+    let mix = cheapest(&reactions, "FUEL", 1, &offers).unwrap().unwrap();
+    assert_eq!(mix.cost, 32);
+    assert_eq!(mix.bought.get("ORE"), Some(&30));
+    assert_eq!(mix.bought.get("D"), None);
+
+    // buying D saves 10 ORE and an IRON
+    let offers = vec![
+        Offer::new("ORE", 1000, 1),
+        Offer::new("IRON", 10, 2),
+        Offer::new("D", 5, 5),
+    ];
+    let mix = cheapest(&reactions, "FUEL", 1, &offers).unwrap().unwrap();
+    assert_eq!(mix.cost, 25);
+    assert_eq!(mix.bought.get("D"), Some(&1));
+    assert_eq!(mix.plan.runs("C"), 0);
+    // but only five of them are available, the sixth is produced
+    let mix = cheapest(&reactions, "FUEL", 6, &offers).unwrap().unwrap();
+    assert_eq!(mix.bought.get("D"), Some(&5));
+    assert_eq!(mix.plan.runs("D"), 1);
+    assert_eq!(mix.cost, 25 + 100 + 2);
+
+    let offers = vec![
+        Offer::new("ORE", 1000, 1),
+        Offer::new("IRON", 10, 2),
+        Offer::new("A", 1_000_000, 1),
+        Offer::new("C", 1_000_000, 1),
+    ];
+    // the ORE covers 1000 A, the other 20000 A and all C are bought
+    let mix = cheapest(&reactions, "FUEL", 1000, &offers)
+        .unwrap()
+        .unwrap();
+    assert_eq!(mix.cost, 22000);
+    assert_eq!(mix.bought.get("C"), Some(&1000));
+    assert_eq!(mix.plan.runs("B"), 0);
+
+    // the search agrees with trying every mix of D and C
+    for &amount in &[1, 3, 6, 9] {
+        for &(d_price, c_price) in &[(5, 4), (15, 4), (25, 11), (30, 20)] {
+            let offers = vec![
+                Offer::new("ORE", 1000, 1),
+                Offer::new("IRON", 10, 2),
+                Offer::new("D", 5, d_price),
+                Offer::new("C", 3, c_price),
+            ];
+            let order = reactions.order("FUEL").unwrap();
+            let mut best: Option<u64> = None;
+            for d in 0..=5 {
+                for c in 0..=3 {
+                    let supply = vec![("D".to_string(), d), ("C".to_string(), c)]
+                        .into_iter()
+                        .collect();
+                    let plan = reactions
+                        .plan_supplied(&order, "FUEL", amount, &supply)
+                        .unwrap();
+                    let iron = plan.raw("IRON");
+                    if plan.raw("ORE") > 1000 || iron > 10 {
+                        continue;
+                    }
+                    let cost = plan.raw("ORE")
+                        + 2 * iron
+                        + d_price * plan.bought("D")
+                        + c_price * plan.bought("C");
+                    best = Some(best.map_or(cost, |b: u64| b.min(cost)));
+                }
+            }
+            let mix = cheapest(&reactions, "FUEL", amount, &offers).unwrap();
+            assert_eq!(mix.map(|m| m.cost), best);
+        }
+    }
+
+    let offers = vec![Offer::new("ORE", 1000, 1)];
+    assert_eq!(cheapest(&reactions, "FUEL", 1, &offers), Ok(None));
+    assert_eq!(
+        cheapest(&reactions, "GOLD", 1, &offers),
+        Err(Error::Unreachable("GOLD".into()))
+    );
+
+    // the puzzle input, with a fifth of its chemicals offered a bit below their cost
+    let recipe = parse_input(include_str!("../input"));
+    let reactions = Reactions::new(&recipe, &["ORE"]);
+    let mut offers = vec![Offer::new("ORE", u64::MAX, 1)];
+    for (i, (product, _)) in reactions.iter().into_iter().enumerate() {
+        if product.name != "FUEL" && i % 5 == 0 {
+            let cost = analysis::unit_cost(&reactions, &product.name, "ORE").unwrap();
+            offers.push(Offer::new(&product.name, 20 + 13 * i as u64, cost * 9 / 10));
+        }
+    }
+    let produced = reactions.plan("FUEL", 100).unwrap().raw("ORE");
+    let mix = cheapest(&reactions, "FUEL", 100, &offers).unwrap().unwrap();
+    assert!(mix.cost < produced);
+    for o in &offers[1..] {
+        assert!(mix.plan.bought(&o.name) <= o.stock);
+    }
+}
@@ -1,248 +1,95 @@
-use itertools::Itertools;
-use std::collections::HashMap;
+use day14::analysis;
+use day14::reaction::{parse_ingredients, parse_input, Ingredient, Reactions};
+use std::env;
+use std::fs;
 
-type Recipe = HashMap<Ingredient, Vec<Ingredient>>;
-
-#[derive(Hash, PartialEq, Eq, Clone, Debug)]
-struct Ingredient {
-    name: String,
-    amount: u64,
-}
-
-impl Ingredient {
-    fn new() -> Ingredient {
-        Ingredient {
-            name: "NONE".to_string(),
-            amount: 0,
-        }
-    }
-}
-
-fn topological_sort(of: &str, fr: &str, from: &Recipe) -> Vec<Ingredient> {
-    let wanted = match get_by_name(fr, from) {
-        Some(i) => i,
-        _ => return vec![],
-    };
-    // find the leafes of the graph
-    let mut recipes = from.clone();
-    let mut sorted_products = recipes
-        .iter()
-        .filter(|(_, v)| v.len() == 1 && v[0].name == of)
-        .map(|(k, _)| k)
-        .cloned()
-        .collect::<Vec<_>>();
-
-    sorted_products.iter().for_each(|ingr| {
-        recipes.remove(ingr);
-        ()
-    });
-
-    while !sorted_products.contains(&wanted) {
-        let mut producable = recipes
-            .iter()
-            .filter(|(_, v)| {
-                v.len() <= sorted_products.len()
-                    && v.iter()
-                        .all(|ingr| sorted_products.iter().any(|given| ingr.name == given.name))
-            })
-            .map(|(k, _)| k)
-            .cloned()
-            .collect::<Vec<_>>();
-
-        producable.iter().for_each(|ingr| {
-            recipes.remove(ingr);
-            ()
-        });
-        sorted_products.append(&mut producable);
-    }
+fn main() {
+    println!("--- Day 14: Space Stoichiometry ---\n");
 
-    // println!("Sorted products:");
-    // for p in &sorted_products {
-    //     println!("-- {:?}", p);
-    // }
+    println!("Reading input...");
 
-    return sorted_products;
-}
+    let input = include_str!("../input");
 
-fn get_by_name(name: &str, rec: &Recipe) -> Option<Ingredient> {
-    for k in rec.keys() {
-        if k.name == name {
-            return Some(k.clone());
+    println!("Parsing input...");
+    let ingrmap = parse_input(input);
+
+    // `day14 [analyze] [dot FILE] [stock "10 ORE, 2 LMPH"]` prints where the
+    // ORE goes, writes the reactions as a Graphviz graph and finds the most
+    // FUEL the stock of raw materials is good for.
+    let mut analyze = false;
+    let mut dot = None;
+    let mut stock = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "analyze" => analyze = true,
+            "dot" => dot = Some(args.next().expect("dot needs a file name")),
+            "stock" => stock = Some(parse_ingredients(&args.next().expect("stock needs a list"))),
+            s => panic!("Unknown argument: {}", s),
         }
     }
-    return None;
-}
-
-fn get_min_amount(of: &str, fr: &str, from: &Recipe) -> Option<Ingredient> {
-    let mut product_chain = topological_sort(of, fr, from);
 
-    let mut resolved: Vec<Ingredient> = vec![];
-    while let Some(current) = product_chain.pop() {
-        // !product_chain.is_empty() {
-        // let current = product_chain.pop().unwrap();
-        let mut reactants = from.get(&current).unwrap().clone();
-
-        let mut scale = 1;
-        if let Some(ind) = resolved.iter().position(|i| i.name == current.name) {
-            let ingr = resolved.get(ind).unwrap();
-            if ingr.amount > current.amount {
-                scale = (ingr.amount as f64 / current.amount as f64).ceil() as u64;
-            }
-            resolved.remove(ind);
-        }
-
-        // println!("CURRENT:   {:?}", current);
-        // println!("REACTANTS: {:?}", reactants);
-        // println!("SCALE:     {}", scale);
+    println!("\n--- Part 1: ---\n");
 
-        for react in reactants.drain(0..) {
-            if let Some(ind) = resolved.iter().position(|i| i.name == react.name) {
-                let ingr = resolved.get_mut(ind).unwrap();
-                ingr.amount += react.amount * scale;
-            } else {
-                let mut react = react;
-                react.amount *= scale;
-                resolved.push(react);
+    let reactions = Reactions::new(&ingrmap, &["ORE"]);
+    match reactions.plan("FUEL", 1) {
+        Ok(plan) => {
+            println!("Reaction needs {} amount of ORE.", plan.raw("ORE"));
+            let mut runs = plan.runs.iter().collect::<Vec<_>>();
+            runs.sort();
+            println!("\nReactions run:");
+            for (name, n) in runs {
+                println!("{:>3} x {}", n, name);
             }
         }
-        // println!("-> {:?}\n--", resolved);
+        Err(e) => println!("No reaction found: {}", e),
     }
 
-    // println!("RESOLVED: {:?}", resolved);
+    println!("\n--- Part 2: ---\n");
 
-    if let Some(ind) = resolved.iter().position(|i| i.name == of) {
-        let ingr = resolved.get(ind).unwrap();
-        Some(ingr.clone())
-    } else {
-        None
+    let cargo_ore = Ingredient {
+        name: "ORE".to_string(),
+        amount: 1000000000000_u64,
+    };
+    match reactions.max_producible("FUEL", &cargo_ore) {
+        Ok(fuel) => println!("{} amount of FUEL can be produced.", fuel),
+        Err(e) => println!("No fuel can be produced: {}", e),
     }
-}
-
-fn get_max_amount(of: &str, fr: &Ingredient, from: &Recipe) -> Option<Ingredient> {
-    let low = 0;
-    let high = fr.amount;
 
-    fn bsearch(start: u64, end: u64, of: &str, fr: &str, rec: &Recipe) -> u64 {
-        if start == end - 1 {
-            return start;
+    if analyze {
+        println!("\n--- Analysis: ---\n");
+        match analysis::critical_path(&reactions, "FUEL") {
+            Ok(path) => println!(
+                "Critical path ({} reactions): {}",
+                path.len() - 1,
+                path.join(" <- ")
+            ),
+            Err(e) => println!("No critical path: {}", e),
         }
-        let scale = (end - start) / 2 + start;
-
-        let mut product_chain = topological_sort(of, fr, rec);
-
-        let mut resolved: Vec<Ingredient> = vec![Ingredient {
-            name: "FUEL".to_string(),
-            amount: scale,
-        }];
-        while let Some(current) = product_chain.pop() {
-            let mut reactants = rec.get(&current).unwrap().clone();
-
-            let mut scale = 1;
-            if let Some(ind) = resolved.iter().position(|i| i.name == current.name) {
-                let ingr = resolved.get(ind).unwrap();
-                if ingr.amount > current.amount {
-                    scale = (ingr.amount as f64 / current.amount as f64).ceil() as u64;
-                }
-                resolved.remove(ind);
+        if let Ok(waste) = analysis::waste(&reactions, "FUEL", 1, "ORE") {
+            println!("\nLeftovers of one FUEL:");
+            for w in waste.iter().take(5) {
+                println!("{:>6} ORE in {} {}", w.cost, w.amount, w.name);
             }
-
-            // println!("CURRENT:   {:?}", current);
-            // println!("REACTANTS: {:?}", reactants);
-            // println!("SCALE:     {}", scale);
-
-            for react in reactants.drain(0..) {
-                if let Some(ind) = resolved.iter().position(|i| i.name == react.name) {
-                    let ingr = resolved.get_mut(ind).unwrap();
-                    ingr.amount += react.amount * scale;
-                } else {
-                    let mut react = react;
-                    react.amount *= scale;
-                    resolved.push(react);
-                }
-            }
-            // println!("-> {:?}\n--", resolved);
         }
-
-        // println!("RESOLVED: {:?}", resolved);
-
-        if let Some(ind) = resolved.iter().position(|i| i.name == of) {
-            let ingr = resolved.get_mut(ind).unwrap();
-            if ingr.amount > 1000000000000_u64 {
-                bsearch(start, scale, of, fr, rec)
-            } else {
-                bsearch(scale, end, of, fr, rec)
+        for &n in &[1, 1000] {
+            if let Ok(cost) = analysis::marginal_cost(&reactions, "FUEL", n, "ORE") {
+                println!("\nFUEL number {} costs {} ORE.", n + 1, cost);
             }
-        } else {
-            0
         }
     }
-
-    Some(Ingredient {
-        name: of.to_string(),
-        amount: bsearch(low, high, &fr.name, of, from),
-    })
-}
-
-fn parse_input(s: &str) -> HashMap<Ingredient, Vec<Ingredient>> {
-    let mut map = HashMap::new();
-
-    for line in s.lines() {
-        let parts = line.split(" => ").collect::<Vec<_>>();
-        let ops = parts[0];
-        let prod = parts[1];
-
-        let mut product = Ingredient::new();
-        for (amount, name) in prod.split(' ').tuples() {
-            let amount = amount.parse::<u64>().expect("Couldn't parse amount.");
-            product = Ingredient {
-                name: name.to_string(),
-                amount,
-            };
-        }
-
-        let mut v = vec![];
-        for (amount, name) in ops.split(" ").tuples() {
-            let amount = amount.parse::<u64>().expect("Couldn't parse amount.");
-            v.push(Ingredient {
-                name: name.trim_end_matches(',').to_string(),
-                amount,
-            });
+    if let Some(stock) = stock {
+        let mut raw = stock.iter().map(|s| s.name.as_str()).collect::<Vec<_>>();
+        raw.push("ORE");
+        match reactions.with_raw(&raw).max_producible_from("FUEL", &stock) {
+            Ok(fuel) => println!("\nThe stock is good for {} FUEL.", fuel),
+            Err(e) => println!("\nNo fuel can be produced from the stock: {}", e),
         }
-
-        map.insert(product, v);
-    }
-
-    return map;
-}
-
-fn main() {
-    println!("--- Day 14: Space Stoichiometry ---\n");
-
-    println!("Reading input...");
-
-    let input = include_str!("../input");
-
-    println!("Parsing input...");
-    let ingrmap = parse_input(&input);
-
-    println!("\n--- Part 1: ---\n");
-
-    if let Some(ingr) = get_min_amount("ORE", "FUEL", &ingrmap) {
-        println!("Reaction needs {} amount of {}.", ingr.amount, ingr.name);
-    } else {
-        println!("No reaction found!");
     }
-
-    println!("\n--- Part 2: ---\n");
-
-    let cargo_ore = Ingredient {
-        name: "ORE".to_string(),
-        amount: 1000000000000_u64,
-    };
-    if let Some(ingr) = get_max_amount("FUEL", &cargo_ore, &ingrmap) {
-        println!("{} amount of {} can be produced.", ingr.amount, ingr.name);
-    } else {
-        println!("No fuel can be produced!");
+    if let Some(path) = dot {
+        fs::write(&path, analysis::to_dot(&reactions, "ORE"))
+            .unwrap_or_else(|e| panic!("Could not write {}: {}", path, e));
+        println!("\nWrote the reactions to {}.", path);
     }
 }
 
@@ -255,27 +102,23 @@ fn test1() {
 7 A, 1 D => 1 E
 7 A, 1 E => 1 FUEL";
 
-    let recipe = parse_input(&input);
+    let recipe = parse_input(input);
 
     for (k, v) in &recipe {
         println!("{:?} : {:?}", k, v);
     }
 
+    let reactions = Reactions::new(&recipe, &["ORE"]);
     assert_eq!(
-        Some(Ingredient {
+        Some(&Ingredient {
             name: "FUEL".to_string(),
             amount: 1
         }),
-        get_by_name("FUEL", &recipe)
+        reactions.get("FUEL").map(|(product, _)| product)
     );
 
-    assert_eq!(
-        Some(Ingredient {
-            name: "ORE".to_string(),
-            amount: 31
-        }),
-        get_min_amount("ORE", "FUEL", &recipe)
-    );
+    let plan = reactions.plan("FUEL", 1).unwrap();
+    assert_eq!(plan.raw("ORE"), 31);
 }
 
 #[test]
@@ -290,26 +133,21 @@ fn test2() {
 165 ORE => 2 GPVTF
 3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT";
 
-    let recipe = parse_input(&input);
+    let recipe = parse_input(input);
 
-    let ore = get_min_amount("ORE", "FUEL", &recipe).unwrap();
+    let reactions = Reactions::new(&recipe, &["ORE"]);
+    let ore = reactions.plan("FUEL", 1).unwrap().raw("ORE");
     let cargo_ore = Ingredient {
         name: "ORE".to_string(),
         amount: 1000000000000_u64,
     };
-    let fuel = get_max_amount("FUEL", &cargo_ore, &recipe).unwrap();
+    let fuel = reactions.max_producible("FUEL", &cargo_ore).unwrap();
 
     println!("TEST2: {:?}", fuel);
 
-    assert_eq!(
-        ore,
-        Ingredient {
-            name: "ORE".to_string(),
-            amount: 13312
-        },
-    );
+    assert_eq!(ore, 13312);
 
-    assert_eq!(fuel.amount, 82892753);
+    assert_eq!(fuel, 82892753);
 }
 
 #[test]
@@ -332,28 +170,20 @@ fn test3() {
 7 XCVML => 6 RJRHP
 5 BHXH, 4 VRPVC => 5 LTCX";
 
-    let recipe = parse_input(&input);
+    let recipe = parse_input(input);
 
-    let ore = get_min_amount("ORE", "FUEL", &recipe).unwrap();
+    let reactions = Reactions::new(&recipe, &["ORE"]);
+    let ore = reactions.plan("FUEL", 1).unwrap().raw("ORE");
 
     let cargo_ore = Ingredient {
         name: "ORE".to_string(),
         amount: 1000000000000_u64,
     };
-    let fuel = get_max_amount("FUEL", &cargo_ore, &recipe).unwrap();
+    let fuel = reactions.max_producible("FUEL", &cargo_ore).unwrap();
 
-    println!(
-        "TEST3: {:?}",
-        get_max_amount("FUEL", &cargo_ore, &recipe).unwrap()
-    );
+    println!("TEST3: {:?}", fuel);
 
-    assert_eq!(
-        ore,
-        Ingredient {
-            name: "ORE".to_string(),
-            amount: 2210736
-        }
-    );
+    assert_eq!(ore, 2210736);
 
-    assert_eq!(fuel.amount, 460664);
+    assert_eq!(fuel, 460664);
 }
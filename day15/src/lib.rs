@@ -308,3 +308,870 @@ pub mod icm {
         }
     } // END IMPL Processor
 }
+
+pub mod explore {
+    use crate::icm::Processor;
+    use grid::{Grid, Tile};
+    use std::collections::{HashMap, HashSet, VecDeque};
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::thread;
+
+    // (x, y) with y growing southwards.
+    pub type Coord = (i32, i32);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Dir {
+        North,
+        South,
+        West,
+        East,
+    }
+
+    impl Dir {
+        pub const ALL: [Dir; 4] = [Dir::North, Dir::South, Dir::West, Dir::East];
+
+        pub fn from_id(id: i32) -> Option<Dir> {
+            match id {
+                1 => Some(Dir::North),
+                2 => Some(Dir::South),
+                3 => Some(Dir::West),
+                4 => Some(Dir::East),
+                _ => None,
+            }
+        }
+
+        pub fn id(self) -> i32 {
+            match self {
+                Dir::North => 1,
+                Dir::South => 2,
+                Dir::West => 3,
+                Dir::East => 4,
+            }
+        }
+
+        pub fn reverse(self) -> Dir {
+            match self {
+                Dir::North => Dir::South,
+                Dir::South => Dir::North,
+                Dir::West => Dir::East,
+                Dir::East => Dir::West,
+            }
+        }
+
+        pub fn left(self) -> Dir {
+            match self {
+                Dir::North => Dir::West,
+                Dir::West => Dir::South,
+                Dir::South => Dir::East,
+                Dir::East => Dir::North,
+            }
+        }
+
+        pub fn right(self) -> Dir {
+            self.left().reverse()
+        }
+
+        // The neighbor of c in this direction.
+        pub fn apply(self, c: Coord) -> Coord {
+            let (x, y) = c;
+            match self {
+                Dir::North => (x, y - 1),
+                Dir::South => (x, y + 1),
+                Dir::West => (x - 1, y),
+                Dir::East => (x + 1, y),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Cell {
+        Wall,
+        Open,
+        // An open cell holding what we are looking for, e.g. the oxygen system.
+        Target,
+        Unknown,
+    }
+
+    impl Cell {
+        // Status codes of the repair droid.
+        pub fn from_status(status: i32) -> Option<Cell> {
+            match status {
+                0 => Some(Cell::Wall),
+                1 => Some(Cell::Open),
+                2 => Some(Cell::Target),
+                _ => None,
+            }
+        }
+
+        pub fn is_open(self) -> bool {
+            self == Cell::Open || self == Cell::Target
+        }
+    }
+
+    /*
+    Something that can be moved around a grid and reports what it ran into.
+    Returns the cell in direction dir. Unless it is a wall the oracle has moved
+    onto it.
+     */
+    pub trait Oracle {
+        fn step(&mut self, dir: Dir) -> Cell;
+    }
+
+    impl<F> Oracle for F
+    where
+        F: FnMut(Dir) -> Cell,
+    {
+        fn step(&mut self, dir: Dir) -> Cell {
+            self(dir)
+        }
+    }
+
+    // The repair droid, an Intcode program running in its own thread.
+    pub struct IntcodeOracle {
+        input: Sender<i32>,
+        output: Receiver<i32>,
+    }
+
+    impl IntcodeOracle {
+        pub fn new(program: &[i32]) -> IntcodeOracle {
+            let (send_cpu, recv_cpu) = channel();
+            let (send_droid, recv_droid) = channel();
+            let mut cpu = Processor::new(0, program.to_vec(), recv_cpu, send_droid);
+            thread::spawn(move || cpu.run());
+            IntcodeOracle {
+                input: send_cpu,
+                output: recv_droid,
+            }
+        }
+    }
+
+    impl Oracle for IntcodeOracle {
+        fn step(&mut self, dir: Dir) -> Cell {
+            self.input
+                .send(dir.id())
+                .expect("Could not send direction to the droid.");
+            let status = self
+                .output
+                .recv()
+                .expect("Could not receive status from the droid.");
+            Cell::from_status(status).expect("Droid returned an invalid status.")
+        }
+    }
+
+    /*
+    A known maze, e.g. for tests. Parsed from text where '#' is a wall, '.' an
+    open cell, 'O' the target and 'D' the open cell the droid starts on. The
+    start becomes (0, 0), everything outside the text is a wall.
+     */
+    pub struct GridOracle {
+        cells: HashMap<Coord, Cell>,
+        pos: Coord,
+        steps: usize,
+    }
+
+    impl GridOracle {
+        pub fn parse(s: &str) -> GridOracle {
+            let mut cells = HashMap::new();
+            let mut start = (0, 0);
+            for (y, line) in s.lines().enumerate() {
+                for (x, c) in line.chars().enumerate() {
+                    let c_pos = (x as i32, y as i32);
+                    let cell = match c {
+                        '#' => Cell::Wall,
+                        '.' => Cell::Open,
+                        'O' => Cell::Target,
+                        'D' => {
+                            start = c_pos;
+                            Cell::Open
+                        }
+                        _ => panic!("Unknown map character: {:?}", c),
+                    };
+                    cells.insert(c_pos, cell);
+                }
+            }
+            let cells = cells
+                .into_iter()
+                .map(|((x, y), cell)| ((x - start.0, y - start.1), cell))
+                .collect();
+            GridOracle {
+                cells,
+                pos: (0, 0),
+                steps: 0,
+            }
+        }
+
+        pub fn position(&self) -> Coord {
+            self.pos
+        }
+
+        // Number of move commands received.
+        pub fn steps(&self) -> usize {
+            self.steps
+        }
+    }
+
+    impl Oracle for GridOracle {
+        fn step(&mut self, dir: Dir) -> Cell {
+            self.steps += 1;
+            let next = dir.apply(self.pos);
+            let cell = *self.cells.get(&next).unwrap_or(&Cell::Wall);
+            if cell.is_open() {
+                self.pos = next;
+            }
+            cell
+        }
+    }
+
+    impl Tile for Cell {
+        fn all() -> &'static [Cell] {
+            &[Cell::Wall, Cell::Open, Cell::Target]
+        }
+
+        fn symbol(self) -> char {
+            match self {
+                Cell::Wall => '#',
+                Cell::Open => '.',
+                Cell::Target => 'O',
+                Cell::Unknown => ' ',
+            }
+        }
+
+        fn name(self) -> &'static str {
+            match self {
+                Cell::Wall => "wall",
+                Cell::Open => "open",
+                Cell::Target => "target",
+                Cell::Unknown => "unknown",
+            }
+        }
+
+        fn color(self) -> [u8; 3] {
+            match self {
+                Cell::Wall => [64, 64, 64],
+                Cell::Open => [255, 255, 255],
+                Cell::Target => [0, 120, 255],
+                Cell::Unknown => [0, 0, 0],
+            }
+        }
+    }
+
+    // The explored cells. Can be saved and loaded through its grid.
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct Map {
+        grid: Grid<Cell>,
+    }
+
+    impl Map {
+        pub fn new() -> Map {
+            Map::default()
+        }
+
+        pub fn from_grid(mut grid: Grid<Cell>) -> Map {
+            let unknown = grid
+                .cells()
+                .iter()
+                .filter(|(_, cell)| **cell == Cell::Unknown)
+                .map(|(c, _)| *c)
+                .collect::<Vec<_>>();
+            for c in unknown {
+                grid.remove(c);
+            }
+            Map { grid }
+        }
+
+        pub fn grid(&self) -> &Grid<Cell> {
+            &self.grid
+        }
+
+        pub fn get(&self, c: Coord) -> Cell {
+            self.grid.get(c).unwrap_or(Cell::Unknown)
+        }
+
+        pub fn set(&mut self, c: Coord, cell: Cell) {
+            if cell == Cell::Unknown {
+                self.grid.remove(c);
+            } else {
+                self.grid.set(c, cell);
+            }
+        }
+
+        // All explored cells.
+        pub fn cells(&self) -> &HashMap<Coord, Cell> {
+            self.grid.cells()
+        }
+
+        pub fn target(&self) -> Option<Coord> {
+            self.cells()
+                .iter()
+                .find(|(_, cell)| **cell == Cell::Target)
+                .map(|(c, _)| *c)
+        }
+
+        // Coordinates of all cells which can be walked on.
+        pub fn open_cells(&self) -> impl Iterator<Item = Coord> + '_ {
+            self.cells()
+                .iter()
+                .filter(|(_, cell)| cell.is_open())
+                .map(|(c, _)| *c)
+        }
+
+        // Returns (min, max) coordinates of the explored cells.
+        pub fn bounds(&self) -> Option<(Coord, Coord)> {
+            self.grid.bounds()
+        }
+
+        /*
+        Draws the map, walls as blocks, the target as 'O' and unknown cells as
+        '.'. The droid is drawn as 'X' if its position is given.
+         */
+        pub fn print(&self, droid: Option<Coord>) -> String {
+            self.print_with(|c, cell| match cell {
+                _ if Some(c) == droid => 'X',
+                Cell::Wall => '\u{2588}',
+                Cell::Open => ' ',
+                Cell::Target => 'O',
+                Cell::Unknown => '.',
+            })
+        }
+
+        // Draws the map with a character chosen per cell.
+        pub fn print_with<F: Fn(Coord, Cell) -> char>(&self, symbol: F) -> String {
+            let mut s = String::new();
+            let ((xb, yb), (xg, yg)) = match self.bounds() {
+                Some(b) => b,
+                None => return s,
+            };
+            for y in yb..=yg {
+                for x in xb..=xg {
+                    s.push(symbol((x, y), self.get((x, y))));
+                }
+                s.push('\n');
+            }
+            s
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Strategy {
+        // Walks into unknown cells as deep as possible and backtracks.
+        DepthFirst,
+        // Always walks to the nearest unknown cell next to the explored area.
+        Frontier,
+        /*
+        Keeps the left hand on the wall until it is back where it started.
+        Only maps the walls connected to the start, areas around free-standing
+        walls stay unknown in mazes with loops.
+         */
+        WallFollower,
+    }
+
+    // Maps a grid by moving an oracle around, starting at (0, 0).
+    pub struct Explorer<O: Oracle> {
+        oracle: O,
+        pos: Coord,
+        map: Map,
+        moves: usize,
+    }
+
+    impl<O: Oracle> Explorer<O> {
+        pub fn new(oracle: O) -> Explorer<O> {
+            let mut map = Map::new();
+            map.set((0, 0), Cell::Open);
+            Explorer {
+                oracle,
+                pos: (0, 0),
+                map,
+                moves: 0,
+            }
+        }
+
+        pub fn position(&self) -> Coord {
+            self.pos
+        }
+
+        pub fn map(&self) -> &Map {
+            &self.map
+        }
+
+        pub fn into_map(self) -> Map {
+            self.map
+        }
+
+        pub fn oracle(&self) -> &O {
+            &self.oracle
+        }
+
+        // Number of move commands sent to the oracle.
+        pub fn moves(&self) -> usize {
+            self.moves
+        }
+
+        pub fn explore(&mut self, strategy: Strategy) -> &Map {
+            match strategy {
+                Strategy::DepthFirst => self.depth_first(),
+                Strategy::Frontier => self.frontier(),
+                Strategy::WallFollower => self.follow_wall(),
+            }
+            &self.map
+        }
+
+        // Moves the oracle and records what it found. Returns true if it moved.
+        fn step(&mut self, dir: Dir) -> bool {
+            let next = dir.apply(self.pos);
+            let cell = self.oracle.step(dir);
+            self.moves += 1;
+            self.map.set(next, cell);
+            if cell.is_open() {
+                self.pos = next;
+            }
+            cell.is_open()
+        }
+
+        fn unknown_neighbor(&self) -> Option<Dir> {
+            Dir::ALL
+                .iter()
+                .copied()
+                .find(|d| self.map.get(d.apply(self.pos)) == Cell::Unknown)
+        }
+
+        fn depth_first(&mut self) {
+            // directions taken to get to the current position
+            let mut trail = vec![];
+            loop {
+                if let Some(dir) = self.unknown_neighbor() {
+                    if self.step(dir) {
+                        trail.push(dir);
+                    }
+                } else if let Some(dir) = trail.pop() {
+                    self.step(dir.reverse());
+                } else {
+                    break;
+                }
+            }
+        }
+
+        fn frontier(&mut self) {
+            while let Some(path) = self.path_to_unknown() {
+                for dir in path {
+                    self.step(dir);
+                }
+            }
+        }
+
+        // Shortest path over known open cells into the nearest unknown cell.
+        fn path_to_unknown(&self) -> Option<Vec<Dir>> {
+            let mut came_from: HashMap<Coord, (Coord, Dir)> = HashMap::new();
+            let mut todo = VecDeque::new();
+            todo.push_back(self.pos);
+            while let Some(cur) = todo.pop_front() {
+                for dir in Dir::ALL.iter().copied() {
+                    let next = dir.apply(cur);
+                    if next == self.pos || came_from.contains_key(&next) {
+                        continue;
+                    }
+                    match self.map.get(next) {
+                        Cell::Wall => continue,
+                        Cell::Unknown => {
+                            let mut path = vec![dir];
+                            let mut c = cur;
+                            while let Some((prev, d)) = came_from.get(&c) {
+                                path.push(*d);
+                                c = *prev;
+                            }
+                            path.reverse();
+                            return Some(path);
+                        }
+                        _ => {
+                            came_from.insert(next, (cur, dir));
+                            todo.push_back(next);
+                        }
+                    }
+                }
+            }
+            None
+        }
+
+        fn follow_wall(&mut self) {
+            let mut heading = Dir::North;
+            let mut seen = HashSet::new();
+            while seen.insert((self.pos, heading)) {
+                // prefer left, then straight, right and finally back
+                let turns = [heading.left(), heading, heading.right(), heading.reverse()];
+                let mut moved = false;
+                for dir in turns.iter().copied() {
+                    if self.map.get(dir.apply(self.pos)) != Cell::Wall && self.step(dir) {
+                        heading = dir;
+                        moved = true;
+                        break;
+                    }
+                }
+                if !moved {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+pub mod path {
+    use crate::explore::{Coord, Dir, Map};
+    use std::cmp::Reverse;
+    use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+    // Open neighbors of c with the direction leading to them.
+    fn neighbors(map: &Map, c: Coord) -> impl Iterator<Item = (Dir, Coord)> + '_ {
+        Dir::ALL
+            .iter()
+            .map(move |d| (*d, d.apply(c)))
+            .filter(move |(_, n)| map.get(*n).is_open())
+    }
+
+    // Follows the predecessors back from goal and returns the moves from the start.
+    fn walk_back(came_from: &HashMap<Coord, (Coord, Dir)>, goal: Coord) -> Vec<Dir> {
+        let mut path = vec![];
+        let mut c = goal;
+        while let Some((prev, d)) = came_from.get(&c) {
+            path.push(*d);
+            c = *prev;
+        }
+        path.reverse();
+        path
+    }
+
+    // Number of moves from start to every reachable open cell, start included.
+    pub fn distances(map: &Map, start: Coord) -> HashMap<Coord, u32> {
+        let mut dist = HashMap::new();
+        dist.insert(start, 0);
+        let mut todo = VecDeque::new();
+        todo.push_back(start);
+        while let Some(cur) = todo.pop_front() {
+            let d = dist[&cur];
+            for (_, next) in neighbors(map, cur) {
+                dist.entry(next).or_insert_with(|| {
+                    todo.push_back(next);
+                    d + 1
+                });
+            }
+        }
+        dist
+    }
+
+    /*
+    Cheapest cost from start to every reachable open cell.
+    cost - Cost of moving onto a cell.
+     */
+    pub fn dijkstra<F: Fn(Coord) -> u32>(map: &Map, start: Coord, cost: F) -> HashMap<Coord, u32> {
+        let mut dist = HashMap::new();
+        let mut todo = BinaryHeap::new();
+        todo.push(Reverse((0, start)));
+        while let Some(Reverse((d, cur))) = todo.pop() {
+            if dist.contains_key(&cur) {
+                continue;
+            }
+            dist.insert(cur, d);
+            for (_, next) in neighbors(map, cur) {
+                if !dist.contains_key(&next) {
+                    todo.push(Reverse((d + cost(next), next)));
+                }
+            }
+        }
+        dist
+    }
+
+    // Moves of a shortest path from start to goal, found by breadth first search.
+    pub fn shortest_path(map: &Map, start: Coord, goal: Coord) -> Option<Vec<Dir>> {
+        let mut came_from = HashMap::new();
+        let mut todo = VecDeque::new();
+        todo.push_back(start);
+        while let Some(cur) = todo.pop_front() {
+            if cur == goal {
+                return Some(walk_back(&came_from, goal));
+            }
+            for (dir, next) in neighbors(map, cur) {
+                if next != start && !came_from.contains_key(&next) {
+                    came_from.insert(next, (cur, dir));
+                    todo.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    // Like shortest_path, but searches towards the goal first.
+    pub fn a_star(map: &Map, start: Coord, goal: Coord) -> Option<Vec<Dir>> {
+        let estimate = |c: Coord| ((c.0 - goal.0).abs() + (c.1 - goal.1).abs()) as u32;
+        let mut came_from = HashMap::new();
+        let mut dist = HashMap::new();
+        dist.insert(start, 0);
+        let mut todo = BinaryHeap::new();
+        todo.push(Reverse((estimate(start), start)));
+        while let Some(Reverse((_, cur))) = todo.pop() {
+            if cur == goal {
+                return Some(walk_back(&came_from, goal));
+            }
+            let d = dist[&cur] + 1;
+            for (dir, next) in neighbors(map, cur) {
+                let shorter = match dist.get(&next) {
+                    Some(old) => d < *old,
+                    None => true,
+                };
+                if shorter {
+                    dist.insert(next, d);
+                    came_from.insert(next, (cur, dir));
+                    todo.push(Reverse((d + estimate(next), next)));
+                }
+            }
+        }
+        None
+    }
+
+    // Distance to the farthest reachable cell, e.g. the minutes oxygen needs to fill the map.
+    pub fn eccentricity(map: &Map, start: Coord) -> u32 {
+        distances(map, start).values().copied().max().unwrap_or(0)
+    }
+}
+
+pub mod oxygen {
+    use crate::explore::{Cell, Coord, Dir, Map};
+    use std::collections::HashSet;
+
+    // Oxygen spreading from a source to all open neighbors once per minute.
+    pub struct Simulation<'a> {
+        map: &'a Map,
+        filled: HashSet<Coord>,
+        front: Vec<Coord>,
+        minute: u32,
+    }
+
+    impl<'a> Simulation<'a> {
+        pub fn new(map: &'a Map, source: Coord) -> Simulation<'a> {
+            let mut filled = HashSet::new();
+            filled.insert(source);
+            Simulation {
+                map,
+                filled,
+                front: vec![source],
+                minute: 0,
+            }
+        }
+
+        pub fn minute(&self) -> u32 {
+            self.minute
+        }
+
+        pub fn filled(&self) -> &HashSet<Coord> {
+            &self.filled
+        }
+
+        // Cells the oxygen reached in the last minute.
+        pub fn front(&self) -> &[Coord] {
+            &self.front
+        }
+
+        /*
+        Lets one minute pass. Returns false without counting the minute if the
+        oxygen cannot spread any further.
+         */
+        pub fn step(&mut self) -> bool {
+            let mut front = vec![];
+            for c in &self.front {
+                for d in Dir::ALL.iter() {
+                    let next = d.apply(*c);
+                    if self.map.get(next).is_open() && self.filled.insert(next) {
+                        front.push(next);
+                    }
+                }
+            }
+            if front.is_empty() {
+                return false;
+            }
+            front.sort();
+            self.front = front;
+            self.minute += 1;
+            true
+        }
+
+        /*
+        Runs until every reachable cell is filled. Returns the minutes it took,
+        the cells filled last are in front() afterwards.
+         */
+        pub fn run(&mut self) -> u32 {
+            while self.step() {}
+            self.minute
+        }
+
+        // Draws the map with oxygen as 'o' and the cells just reached as '*'.
+        pub fn print(&self) -> String {
+            let front = self.front.iter().collect::<HashSet<_>>();
+            self.map.print_with(|c, cell| match cell {
+                _ if front.contains(&c) => '*',
+                _ if self.filled.contains(&c) => 'o',
+                Cell::Wall => '\u{2588}',
+                Cell::Unknown => '.',
+                _ => ' ',
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+const LOOP_MAZE: &str = "\
+#######
+#D....#
+#.###.#
+#...#O#
+###...#
+#######";
+
+#[test]
+fn test_explore_strategies() {
+    use explore::{Cell, Explorer, GridOracle, Strategy};
+
+    for strategy in &[Strategy::DepthFirst, Strategy::Frontier] {
+        let mut explorer = Explorer::new(GridOracle::parse(LOOP_MAZE));
+        let map = explorer.explore(*strategy).clone();
+
+        assert_eq!(map.target(), Some((4, 2)), "{:?}", strategy);
+        assert_eq!(map.open_cells().count(), 14, "{:?}", strategy);
+        assert_eq!(map.get((1, 1)), Cell::Wall);
+        assert_eq!(map.get((6, 0)), Cell::Unknown);
+        assert_eq!(explorer.moves(), explorer.oracle().steps());
+        assert_eq!(explorer.position(), explorer.oracle().position());
+        assert_eq!(
+            map.print(Some((0, 0))),
+            "\
+.█████.
+█X    █
+█ ███ █
+█   █O█
+.██   █
+...███.
+"
+        );
+    }
+}
+
+#[test]
+fn test_wall_follower() {
+    use explore::{Cell, Dir, Explorer, GridOracle, Strategy};
+
+    let maze = "\
+#######
+#D.#..#
+#.#..##
+#O..###
+#######";
+    let mut explorer = Explorer::new(GridOracle::parse(maze));
+    let map = explorer.explore(Strategy::WallFollower);
+    assert_eq!(map.target(), Some((0, 2)));
+    assert_eq!(map.open_cells().count(), 10);
+    assert_eq!(explorer.position(), (0, 0));
+
+    // a closure as oracle: a corridor going east, the target at its end
+    let mut x = 0;
+    let mut corridor = |dir| match dir {
+        Dir::East if x < 3 => {
+            x += 1;
+            if x == 3 {
+                Cell::Target
+            } else {
+                Cell::Open
+            }
+        }
+        Dir::West if x > 0 => {
+            x -= 1;
+            Cell::Open
+        }
+        _ => Cell::Wall,
+    };
+    let mut explorer = Explorer::new(&mut corridor);
+    assert_eq!(
+        explorer.explore(Strategy::DepthFirst).target(),
+        Some((3, 0))
+    );
+}
+
+#[test]
+fn test_path() {
+    use explore::{Cell, Explorer, GridOracle, Oracle, Strategy};
+
+    let mut explorer = Explorer::new(GridOracle::parse(LOOP_MAZE));
+    let map = explorer.explore(Strategy::DepthFirst).clone();
+    let target = map.target().unwrap();
+
+    let dist = path::distances(&map, (0, 0));
+    assert_eq!(dist.len(), 14);
+    assert_eq!(dist[&(0, 0)], 0);
+    // the loop leads to the target both ways, the eastern one is shorter
+    assert_eq!(dist[&target], 6);
+    assert_eq!(dist[&(2, 3)], 5);
+    assert_eq!(path::dijkstra(&map, (0, 0), |_| 1), dist);
+    assert_eq!(path::eccentricity(&map, (0, 0)), 7);
+    assert_eq!(path::eccentricity(&map, target), 7);
+
+    // making the east side expensive leads the way through the west
+    let weighted = path::dijkstra(&map, (0, 0), |c| if c.0 > 3 { 10 } else { 1 });
+    assert_eq!(weighted[&target], 26);
+
+    for path in &[
+        path::shortest_path(&map, (0, 0), target).unwrap(),
+        path::a_star(&map, (0, 0), target).unwrap(),
+    ] {
+        assert_eq!(path.len(), 6);
+        let mut droid = GridOracle::parse(LOOP_MAZE);
+        let cells = path.iter().map(|d| droid.step(*d)).collect::<Vec<_>>();
+        assert_eq!(cells.last(), Some(&Cell::Target));
+        assert!(cells.iter().all(|c| c.is_open()));
+    }
+    assert_eq!(path::shortest_path(&map, (0, 0), (0, 0)), Some(vec![]));
+    assert_eq!(path::shortest_path(&map, (0, 0), (9, 9)), None);
+    assert_eq!(path::a_star(&map, (0, 0), (-1, 0)), None);
+}
+
+#[test]
+fn test_oxygen() {
+    use explore::{Explorer, GridOracle, Strategy};
+    use oxygen::Simulation;
+
+    let mut explorer = Explorer::new(GridOracle::parse(LOOP_MAZE));
+    let map = explorer.explore(Strategy::DepthFirst).clone();
+    let target = map.target().unwrap();
+
+    let mut sim = Simulation::new(&map, target);
+    assert!(sim.step());
+    assert!(sim.step());
+    assert_eq!(sim.minute(), 2);
+    assert_eq!(sim.front(), &[(3, 3), (4, 0)]);
+    assert_eq!(
+        sim.print(),
+        "\
+.█████.
+█    *█
+█ ███o█
+█   █o█
+.██ *o█
+...███.
+"
+    );
+
+    assert_eq!(sim.run(), path::eccentricity(&map, target));
+    assert_eq!(sim.front(), &[(0, 1)]);
+    assert_eq!(sim.filled().len(), 14);
+    assert!(!sim.step());
+    assert_eq!(sim.minute(), 7);
+}
+
+#[test]
+fn test_map_export() {
+    use explore::{Explorer, GridOracle, Map, Strategy};
+    use grid::Grid;
+
+    let mut explorer = Explorer::new(GridOracle::parse(LOOP_MAZE));
+    let map = explorer.explore(Strategy::DepthFirst).clone();
+
+    let text = map.grid().to_text();
+    assert!(text.ends_with("\n #####\n#.....#\n#.###.#\n#...#O#\n ##...#\n   ###\n"));
+    assert_eq!(Map::from_grid(Grid::from_text(&text).unwrap()), map);
+    let json = map.grid().to_json();
+    assert_eq!(Map::from_grid(Grid::from_json(&json).unwrap()), map);
+}
@@ -1,182 +1,20 @@
-use day15::icm::Processor;
-use itertools::Itertools;
-use itertools::MinMaxResult;
-use std::collections::{HashMap, HashSet};
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::{Arc, Mutex};
+use day15::explore::{Explorer, IntcodeOracle, Map, Strategy};
+use day15::oxygen::Simulation;
+use day15::path;
+use grid::Grid;
+use std::env;
 use std::thread;
+use std::time::Duration;
 
-type Coord = (i32, i32);
-type Map = HashMap<Coord, i32>;
+const FRAME_TIME: Duration = Duration::from_millis(30);
 
-struct Tracker {
-    pos: Coord,
-    visited: HashSet<Coord>,
-    map: Map,
-    input: Receiver<i32>,
-    output: Sender<i32>,
-}
-
-impl Tracker {
-    fn new(input: Receiver<i32>, output: Sender<i32>) -> Self {
-        let mut init = HashMap::new();
-        init.insert((0, 0), 1);
-        Tracker {
-            pos: (0, 0),
-            visited: HashSet::new(),
-            map: init,
-            input,
-            output,
-        }
-    }
-
-    fn get_oxygen_system(&self) -> Option<Coord> {
-        let sys = self
-            .map
-            .iter()
-            .filter(|(_, v)| **v == 2)
-            .map(|(k, _)| k)
-            .collect::<Vec<_>>();
-        if let Some(c) = sys.get(0) {
-            Some(**c)
-        } else {
-            None
-        }
-    }
-
-    fn get_paths(&self) -> Vec<Coord> {
-        self.map
-            .iter()
-            .filter(|(_, v)| **v > 0)
-            .map(|(k, _)| k)
-            .copied()
-            .collect()
-    }
-
-    fn neighbors(&self) -> Vec<(Coord, i32)> {
-        let (x, y) = self.pos;
-        vec![
-            ((x, y + 1), 1),
-            ((x, y - 1), 2),
-            ((x - 1, y), 3),
-            ((x + 1, y), 4),
-        ]
-        .iter()
-        .copied()
-        .filter(|c| !self.visited.contains(&c.0))
-        .collect()
-    }
-
-    fn mv(&self, dir: i32) -> i32 {
-        // println!("Moving in {}", dir);
-        self.output.send(dir).expect("Could not send dir to cpu");
-        self.input.recv().expect("Could not receive from cpu")
-    }
-
-    fn map(&mut self) {
-        // use flood fill to create a map
-        let mut todo = vec![(self.pos, 0)];
-        self.visited.insert(self.pos);
-
-        while let Some((coord, dir)) = todo.pop() {
-            if dir != 0 {
-                let stat = self.mv(dir);
-                self.map.insert(coord, stat);
-                if stat == 1 {
-                    // safe current position
-                    let prev_pos = self.pos;
-                    self.pos = coord;
-                    // map from new position
-                    self.map();
-                    // go back to previous position
-                    self.pos = prev_pos;
-                    self.mv(Tracker::rev_dir(dir));
-                } else if stat == 2 {
-                    self.visited.insert(coord);
-                    self.mv(Tracker::rev_dir(dir));
-                } else {
-                    self.visited.insert(coord);
-                }
-            }
-
-            for (neigh, dir) in self.neighbors() {
-                todo.push((neigh, dir));
-            }
-            // self.print_map();
-        }
-    }
-
-    // Returns (min, max) Coordinates of the map.
-    fn get_map_boundaries(&self) -> Option<(Coord, Coord)> {
-        let minmax_x = self.map.keys().map(|(x, _)| x).minmax();
-        let minmax_y = self.map.keys().map(|(_, y)| y).minmax();
-        match (minmax_x, minmax_y) {
-            (MinMaxResult::MinMax(xb, xg), MinMaxResult::MinMax(yb, yg)) => {
-                Some(((*xb, *yb), (*xg, *yg)))
-            }
-            _ => None,
-        }
-    }
-
-    fn print_map(&self) {
-        let mut s = String::new();
-        if let Some(((xb, yb), (xg, yg))) = self.get_map_boundaries() {
-            for y in yb..=yg {
-                for x in xb..=xg {
-                    if (x, y) == self.pos {
-                        s.push('X');
-                        continue;
-                    }
-                    if let Some(n) = self.map.get(&(x, y)) {
-                        match n {
-                            0 => s.push('\u{2588}'),
-                            1 => s.push(' '),
-                            2 => s.push('O'),
-                            _ => s.push('!'),
-                        }
-                    } else {
-                        s.push('.');
-                    }
-                }
-                s.push('\n');
-            }
-            // print!("\x1B[2J");
-            print!("{}", s);
-        } else {
-            println!("No map available.");
-        }
-    }
-
-    fn rev_dir(dir: i32) -> i32 {
-        match dir {
-            1 => 2,
-            2 => 1,
-            3 => 4,
-            4 => 3,
-            _ => panic!("Unknown direction: {:?}", dir),
-        }
-    }
-}
-
-fn neighbors(c: &Coord) -> Vec<Coord> {
-    let (x, y) = *c;
-    vec![(x, y + 1), (x, y - 1), (x - 1, y), (x + 1, y)]
-}
-
-fn path_lengths(start: &Coord, paths: &[Coord]) -> HashMap<Coord, u32> {
-    let mut map = HashMap::new();
-
-    let mut todo = vec![(*start, 0)];
-    while let Some((cur, plen)) = todo.pop() {
-        neighbors(&cur).iter().for_each(|c| {
-            if paths.contains(c) && !map.contains_key(c) {
-                map.insert(*c, plen + 1);
-                todo.push((*c, plen + 1));
-            }
+fn parse_input(s: &str) -> Vec<i32> {
+    s.split(',')
+        .map(|n| match n.trim().parse::<i32>() {
+            Err(e) => panic!("Could not parse {}: {}", n, e),
+            Ok(i) => i,
         })
-    }
-
-    map
+        .collect()
 }
 
 fn main() {
@@ -186,47 +24,101 @@ fn main() {
 
     let input = include_str!("../input");
 
-    let (send_cpu, cpu_in) = channel();
-    let (cpu_out, recv_cpu) = channel();
-
-    let cpu = Arc::new(Mutex::new(Processor::new(0, vec![], cpu_in, cpu_out)));
-    {
-        let mut cpu = cpu.lock().unwrap();
-        cpu.memory_from_str(&input);
-    }
+    let program = parse_input(input);
 
     println!("\n--- Part 1: ---\n");
 
-    let cpu_handle = Arc::clone(&cpu);
-    thread::spawn(move || {
-        let mut cpu = cpu_handle.lock().unwrap();
-        cpu.run();
-    });
-
-    let mut tracker = Tracker::new(recv_cpu, send_cpu);
+    // `day15 [dfs|bfs|wall] [animate] [save FILE] [load FILE]` selects the
+    // exploration strategy, shows the oxygen spreading minute by minute and
+    // saves the explored map or loads it instead of exploring. Maps are
+    // stored as .txt, .json or .png.
+    let mut strategy = Strategy::DepthFirst;
+    let mut animate = false;
+    let mut save = None;
+    let mut load = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "dfs" => strategy = Strategy::DepthFirst,
+            "bfs" => strategy = Strategy::Frontier,
+            "wall" => strategy = Strategy::WallFollower,
+            "animate" => animate = true,
+            "save" => save = Some(args.next().expect("save needs a file name")),
+            "load" => load = Some(args.next().expect("load needs a file name")),
+            s => panic!("Unknown argument: {}", s),
+        }
+    }
 
-    // map the station
-    tracker.map();
+    let map = if let Some(path) = load {
+        let grid = Grid::load(&path).unwrap_or_else(|e| panic!("Could not load {}: {}", path, e));
+        let map = Map::from_grid(grid);
+        println!("Ship layout from {}:\n", path);
+        print!("{}", map.print(Some((0, 0))));
+        map
+    } else {
+        let mut explorer = Explorer::new(IntcodeOracle::new(&program));
+
+        // map the station
+        explorer.explore(strategy);
+
+        println!("Ship layout:\n");
+        print!("{}", explorer.map().print(Some(explorer.position())));
+        println!(
+            "\nRepair Bot (X) is at {:?} after {} moves",
+            explorer.position(),
+            explorer.moves()
+        );
+        explorer.into_map()
+    };
+    if let Some(path) = save {
+        map.grid()
+            .save(&path)
+            .unwrap_or_else(|e| panic!("Could not save {}: {}", path, e));
+        println!("Saved the map to {}.", path);
+    }
 
-    println!("Ship layout:\n");
-    tracker.print_map();
-    println!("\nRepair Bot (X) is at {:?}", tracker.pos);
-    let oxysys = tracker.get_oxygen_system().unwrap();
+    let map = &map;
+    let oxysys = map.target().unwrap();
     println!("Oxygen System (O) is at {:?}", oxysys);
-    let paths = tracker.get_paths();
-    let plen = path_lengths(&(0, 0), &paths);
+    let route = path::shortest_path(map, (0, 0), oxysys).expect("No way to the Oxygen System.");
+    println!("\nOxygen System is {} commands away.", route.len());
     println!(
-        "\nOxygen System is {} commands away.",
-        plen.get(&oxysys).unwrap()
+        "Route: {}",
+        route.iter().map(|d| d.id().to_string()).collect::<String>()
     );
 
     println!("\n--- Part 2: ---\n");
 
-    let oxy_paths = path_lengths(&oxysys, &paths);
-    // get the coord which is the farthest from the oxygen sytem
-    if let Some(v) = oxy_paths.iter().map(|(_, v)| v).max() {
-        println!("It takes {} minutes to fill everything with oxygen.", v);
-    } else {
-        println!("Could not determine farthest point.");
+    let mut sim = Simulation::new(map, oxysys);
+    if animate {
+        while sim.step() {
+            print!("\x1B[2J\x1B[H{}", sim.print());
+            println!("Minute {}", sim.minute());
+            thread::sleep(FRAME_TIME);
+        }
     }
+    let minutes = sim.run();
+    println!(
+        "It takes {} minutes to fill everything with oxygen.",
+        minutes
+    );
+    println!("The last cells to be filled: {:?}", sim.front());
+}
+
+#[test]
+fn test_input() {
+    let program = parse_input(include_str!("../input"));
+    let mut explorer = Explorer::new(IntcodeOracle::new(&program));
+    explorer.explore(Strategy::DepthFirst);
+    let map = explorer.into_map();
+    let oxysys = map.target().unwrap();
+    assert_eq!(
+        path::shortest_path(&map, (0, 0), oxysys).unwrap().len(),
+        242
+    );
+
+    // the fill time is the distance to the cell farthest from the oxygen system
+    let minutes = Simulation::new(&map, oxysys).run();
+    assert_eq!(minutes, 276);
+    assert_eq!(minutes, path::eccentricity(&map, oxysys));
 }
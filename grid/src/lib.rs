@@ -0,0 +1,537 @@
+/*
+A sparse 2D grid of tiles which can be saved and loaded as plain text, PNG
+or JSON.
+
+Coordinates are (x, y) with y growing downwards, the way the grid is written
+as text. Positions without a tile are unknown.
+
+Text: a header with the origin of the top left cell and a legend, an empty
+line and then one line per row. Unknown cells are spaces. Without a header
+the origin is (0, 0).
+PNG: one pixel per cell, unknown cells are transparent. The origin of the
+top left cell is kept in a tEXt chunk with the keyword "origin", without it
+the origin is (0, 0).
+JSON: {"cells": [{"x": 0, "y": 0, "tile": "wall"}, ...]}
+ */
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+pub type Coord = (i32, i32);
+
+const UNKNOWN: char = ' ';
+
+// The kinds of cells a grid can hold.
+pub trait Tile: Copy + PartialEq + Sized + 'static {
+    // All tiles, used to parse and to write the legend.
+    fn all() -> &'static [Self];
+    // Character in the text format, must not be a space.
+    fn symbol(self) -> char;
+    // Name in the legend and in JSON.
+    fn name(self) -> &'static str;
+    fn color(self) -> [u8; 3];
+
+    fn from_symbol(c: char) -> Option<Self> {
+        Self::all().iter().copied().find(|t| t.symbol() == c)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::all().iter().copied().find(|t| t.name() == name)
+    }
+
+    fn from_color(rgb: [u8; 3]) -> Option<Self> {
+        Self::all().iter().copied().find(|t| t.color() == rgb)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Image(image::ImageError),
+    Json(serde_json::Error),
+    // The text has a malformed header line.
+    Header {
+        line: usize,
+    },
+    UnknownSymbol {
+        line: usize,
+        column: usize,
+        symbol: char,
+    },
+    UnknownName(String),
+    UnknownColor {
+        x: u32,
+        y: u32,
+        rgb: [u8; 3],
+    },
+    // The file extension is none of txt, png or json.
+    UnknownFormat(String),
+    // PNG cells need at least one pixel.
+    ZeroScale,
+    // The origin chunk of a PNG is not two numbers.
+    Origin(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Image(e) => write!(f, "{}", e),
+            Error::Json(e) => write!(f, "{}", e),
+            Error::Header { line } => write!(f, "line {}: malformed header", line),
+            Error::UnknownSymbol {
+                line,
+                column,
+                symbol,
+            } => write!(
+                f,
+                "line {}, column {}: unknown tile {:?}",
+                line, column, symbol
+            ),
+            Error::UnknownName(name) => write!(f, "unknown tile '{}'", name),
+            Error::UnknownColor { x, y, rgb } => {
+                write!(f, "pixel ({}, {}): unknown color {:?}", x, y, rgb)
+            }
+            Error::UnknownFormat(ext) => write!(f, "unknown file format '{}'", ext),
+            Error::ZeroScale => write!(f, "the scale must be at least 1"),
+            Error::Origin(text) => write!(f, "malformed origin '{}'", text),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(e: image::ImageError) -> Error {
+        Error::Image(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::Json(e)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonCell {
+    x: i32,
+    y: i32,
+    tile: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonGrid {
+    cells: Vec<JsonCell>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    cells: HashMap<Coord, T>,
+}
+
+impl<T> Default for Grid<T> {
+    fn default() -> Self {
+        Grid {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Tile> Grid<T> {
+    pub fn new() -> Grid<T> {
+        Grid::default()
+    }
+
+    pub fn get(&self, c: Coord) -> Option<T> {
+        self.cells.get(&c).copied()
+    }
+
+    pub fn set(&mut self, c: Coord, tile: T) {
+        self.cells.insert(c, tile);
+    }
+
+    pub fn remove(&mut self, c: Coord) -> Option<T> {
+        self.cells.remove(&c)
+    }
+
+    pub fn cells(&self) -> &HashMap<Coord, T> {
+        &self.cells
+    }
+
+    // Returns (min, max) coordinates of the known cells.
+    pub fn bounds(&self) -> Option<(Coord, Coord)> {
+        let min_x = self.cells.keys().map(|c| c.0).min()?;
+        let max_x = self.cells.keys().map(|c| c.0).max()?;
+        let min_y = self.cells.keys().map(|c| c.1).min()?;
+        let max_y = self.cells.keys().map(|c| c.1).max()?;
+        Some(((min_x, min_y), (max_x, max_y)))
+    }
+
+    pub fn to_text(&self) -> String {
+        let ((xb, yb), (xg, yg)) = self.bounds().unwrap_or(((0, 0), (-1, -1)));
+        let mut s = format!("origin {} {}\n", xb, yb);
+        for t in T::all() {
+            s.push_str(&format!("legend {} {}\n", t.symbol(), t.name()));
+        }
+        s.push('\n');
+        for y in yb..=yg {
+            let row = (xb..=xg)
+                .map(|x| self.get((x, y)).map_or(UNKNOWN, |t| t.symbol()))
+                .collect::<String>();
+            s.push_str(row.trim_end());
+            s.push('\n');
+        }
+        s
+    }
+
+    // Reads the text format. The legend is informational, tiles are
+    // recognized by T::symbol.
+    pub fn from_text(s: &str) -> Result<Grid<T>, Error> {
+        let lines = s
+            .lines()
+            .map(|l| l.trim_end_matches('\r'))
+            .collect::<Vec<_>>();
+        let has_header = match lines.first() {
+            Some(l) => l.starts_with("origin ") || l.starts_with("legend "),
+            None => false,
+        };
+        let mut origin = (0, 0);
+        let mut first_row = 0;
+        if has_header {
+            for (n, line) in lines.iter().enumerate() {
+                first_row = n + 1;
+                if line.is_empty() {
+                    break;
+                }
+                let words = line.split_whitespace().collect::<Vec<_>>();
+                match words.as_slice() {
+                    ["origin", x, y] => match (x.parse(), y.parse()) {
+                        (Ok(x), Ok(y)) => origin = (x, y),
+                        _ => return Err(Error::Header { line: n + 1 }),
+                    },
+                    ["legend", ..] => (),
+                    _ => return Err(Error::Header { line: n + 1 }),
+                }
+            }
+        }
+
+        let mut grid = Grid::new();
+        for (n, line) in lines.iter().enumerate().skip(first_row) {
+            let y = origin.1 + (n - first_row) as i32;
+            for (x, c) in line.chars().enumerate() {
+                if c == UNKNOWN {
+                    continue;
+                }
+                let tile = T::from_symbol(c).ok_or(Error::UnknownSymbol {
+                    line: n + 1,
+                    column: x + 1,
+                    symbol: c,
+                })?;
+                grid.set((origin.0 + x as i32, y), tile);
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut cells = self
+            .cells
+            .iter()
+            .map(|((x, y), t)| JsonCell {
+                x: *x,
+                y: *y,
+                tile: t.name().to_string(),
+            })
+            .collect::<Vec<_>>();
+        cells.sort_by_key(|c| (c.y, c.x));
+        serde_json::to_string(&JsonGrid { cells }).expect("Could not serialize grid.")
+    }
+
+    pub fn from_json(s: &str) -> Result<Grid<T>, Error> {
+        let json: JsonGrid = serde_json::from_str(s)?;
+        let mut grid = Grid::new();
+        for c in json.cells {
+            let tile = T::from_name(&c.tile).ok_or(Error::UnknownName(c.tile))?;
+            grid.set((c.x, c.y), tile);
+        }
+        Ok(grid)
+    }
+
+    // RGBA pixels of the grid, each cell scale x scale pixels. Returns width, height and pixels.
+    pub fn to_rgba(&self, scale: u32) -> (u32, u32, Vec<u8>) {
+        let ((xb, yb), (xg, yg)) = match self.bounds() {
+            Some(b) => b,
+            None => return (0, 0, vec![]),
+        };
+        let width = (xg - xb + 1) as u32 * scale;
+        let height = (yg - yb + 1) as u32 * scale;
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for py in 0..height {
+            for px in 0..width {
+                let c = (xb + (px / scale) as i32, yb + (py / scale) as i32);
+                match self.get(c) {
+                    Some(t) => {
+                        pixels.extend_from_slice(&t.color());
+                        pixels.push(255);
+                    }
+                    None => pixels.extend_from_slice(&[0, 0, 0, 0]),
+                }
+            }
+        }
+        (width, height, pixels)
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P, scale: u32) -> Result<(), Error> {
+        if scale == 0 {
+            return Err(Error::ZeroScale);
+        }
+        let (width, height, pixels) = self.to_rgba(scale);
+        let mut png = vec![];
+        image::png::PNGEncoder::new(&mut png).encode(
+            &pixels,
+            width,
+            height,
+            image::ColorType::RGBA(8),
+        )?;
+        let (x, y) = self.bounds().map_or((0, 0), |b| b.0);
+        let origin = format!("{} {}", x, y);
+        insert_text(&mut png, ORIGIN_KEYWORD, &origin);
+        std::fs::write(path, png)?;
+        Ok(())
+    }
+
+    // Loads an image saved with save_png and the same scale.
+    pub fn load_png<P: AsRef<Path>>(path: P, scale: u32) -> Result<Grid<T>, Error> {
+        if scale == 0 {
+            return Err(Error::ZeroScale);
+        }
+        let png = std::fs::read(path)?;
+        let (ox, oy) = match find_text(&png, ORIGIN_KEYWORD) {
+            Some(text) => {
+                let words = text.split_whitespace().collect::<Vec<_>>();
+                match words.as_slice() {
+                    [x, y] => match (x.parse(), y.parse()) {
+                        (Ok(x), Ok(y)) => (x, y),
+                        _ => return Err(Error::Origin(text)),
+                    },
+                    _ => return Err(Error::Origin(text)),
+                }
+            }
+            None => (0, 0),
+        };
+        let img = image::load_from_memory_with_format(&png, image::ImageFormat::PNG)?.to_rgba();
+        let mut grid = Grid::new();
+        for y in (0..img.height()).step_by(scale as usize) {
+            for x in (0..img.width()).step_by(scale as usize) {
+                let p = img.get_pixel(x, y).0;
+                if p[3] == 0 {
+                    continue;
+                }
+                let rgb = [p[0], p[1], p[2]];
+                let tile = T::from_color(rgb).ok_or(Error::UnknownColor { x, y, rgb })?;
+                grid.set((ox + (x / scale) as i32, oy + (y / scale) as i32), tile);
+            }
+        }
+        Ok(grid)
+    }
+
+    // Saves in the format given by the file extension: txt, json or png (one pixel per cell).
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        match extension(path).as_str() {
+            "txt" => std::fs::write(path, self.to_text())?,
+            "json" => std::fs::write(path, self.to_json())?,
+            "png" => self.save_png(path, 1)?,
+            ext => return Err(Error::UnknownFormat(ext.to_string())),
+        }
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Grid<T>, Error> {
+        let path = path.as_ref();
+        match extension(path).as_str() {
+            "txt" => Grid::from_text(&std::fs::read_to_string(path)?),
+            "json" => Grid::from_json(&std::fs::read_to_string(path)?),
+            "png" => Grid::load_png(path, 1),
+            ext => Err(Error::UnknownFormat(ext.to_string())),
+        }
+    }
+}
+
+const ORIGIN_KEYWORD: &str = "origin";
+
+// PNG chunks follow the 8 byte signature: length, type, data and a CRC of type and data.
+const PNG_SIGNATURE: usize = 8;
+
+// Adds a tEXt chunk right after the IHDR chunk, which always comes first.
+fn insert_text(png: &mut Vec<u8>, keyword: &str, text: &str) {
+    let mut body = b"tEXt".to_vec();
+    body.extend_from_slice(keyword.as_bytes());
+    body.push(0);
+    body.extend_from_slice(text.as_bytes());
+    let mut chunk = ((body.len() - 4) as u32).to_be_bytes().to_vec();
+    chunk.extend_from_slice(&body);
+    chunk.extend_from_slice(&crc32fast::hash(&body).to_be_bytes());
+    let ihdr_len = u32::from_be_bytes([png[8], png[9], png[10], png[11]]) as usize;
+    let at = PNG_SIGNATURE + 12 + ihdr_len;
+    png.splice(at..at, chunk);
+}
+
+// The text of the first tEXt chunk with the keyword.
+fn find_text(png: &[u8], keyword: &str) -> Option<String> {
+    let mut at = PNG_SIGNATURE;
+    while at + 8 <= png.len() {
+        let len = u32::from_be_bytes([png[at], png[at + 1], png[at + 2], png[at + 3]]) as usize;
+        let kind = &png[at + 4..at + 8];
+        let data = png.get(at + 8..at + 8 + len)?;
+        if kind == b"tEXt" {
+            let mut parts = data.splitn(2, |&b| b == 0);
+            if parts.next() == Some(keyword.as_bytes()) {
+                return parts
+                    .next()
+                    .map(|t| String::from_utf8_lossy(t).into_owned());
+            }
+        }
+        if kind == b"IEND" {
+            break;
+        }
+        at += 12 + len;
+    }
+    None
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cell {
+    Wall,
+    Open,
+}
+
+#[cfg(test)]
+impl Tile for Cell {
+    fn all() -> &'static [Cell] {
+        &[Cell::Wall, Cell::Open]
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Cell::Wall => '#',
+            Cell::Open => '.',
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Cell::Wall => "wall",
+            Cell::Open => "open",
+        }
+    }
+
+    fn color(self) -> [u8; 3] {
+        match self {
+            Cell::Wall => [0, 0, 0],
+            Cell::Open => [255, 255, 255],
+        }
+    }
+}
+
+#[cfg(test)]
+fn sample() -> Grid<Cell> {
+    let mut grid = Grid::new();
+    for x in -2..=1 {
+        grid.set((x, -1), Cell::Wall);
+    }
+    grid.set((-2, 0), Cell::Wall);
+    grid.set((-1, 0), Cell::Open);
+    grid
+}
+
+#[test]
+fn test_text() {
+    let grid = sample();
+    let text = grid.to_text();
+    assert_eq!(
+        text,
+        "origin -2 -1\nlegend # wall\nlegend . open\n\n####\n#.\n"
+    );
+    assert_eq!(Grid::from_text(&text).unwrap(), grid);
+    assert_eq!(
+        Grid::<Cell>::from_text("origin -2 -1\r\n\r\n####\r\n#.\r\n").unwrap(),
+        grid
+    );
+    // without a header the top left cell is (0, 0)
+    let plain = Grid::<Cell>::from_text("####\n#.\n").unwrap();
+    assert_eq!(plain.get((1, 1)), Some(Cell::Open));
+    assert_eq!(plain.get((2, 1)), None);
+
+    match Grid::<Cell>::from_text("##\n#x\n") {
+        Err(Error::UnknownSymbol {
+            line: 2,
+            column: 2,
+            symbol: 'x',
+        }) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match Grid::<Cell>::from_text("origin 1\n\n#\n") {
+        Err(Error::Header { line: 1 }) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn test_json() {
+    let grid = sample();
+    let json = grid.to_json();
+    assert!(json.starts_with(r#"{"cells":[{"x":-2,"y":-1,"tile":"wall"},"#));
+    assert_eq!(Grid::from_json(&json).unwrap(), grid);
+    assert!(Grid::<Cell>::from_json(r#"{"cells":[{"x":0,"y":0,"tile":"lava"}]}"#).is_err());
+}
+
+#[test]
+fn test_png() {
+    let grid = sample();
+    let (width, height, pixels) = grid.to_rgba(2);
+    assert_eq!((width, height), (8, 4));
+    // (-1, 0) is open, (0, 0) unknown
+    assert_eq!(
+        &pixels[(2 * 8 + 2) * 4..(2 * 8 + 3) * 4],
+        &[255, 255, 255, 255]
+    );
+    assert_eq!(&pixels[(3 * 8 + 5) * 4..(3 * 8 + 6) * 4], &[0, 0, 0, 0]);
+
+    let path = std::env::temp_dir().join("grid_test_png.png");
+    grid.save_png(&path, 2).unwrap();
+    let loaded = Grid::<Cell>::load_png(&path, 2).unwrap();
+    assert_eq!(loaded, grid);
+    assert!(matches!(
+        Grid::<Cell>::load_png(&path, 0),
+        Err(Error::ZeroScale)
+    ));
+    std::fs::remove_file(&path).unwrap();
+
+    // without an origin chunk the top left cell is (0, 0)
+    let (width, height, pixels) = grid.to_rgba(1);
+    image::save_buffer(&path, &pixels, width, height, image::ColorType::RGBA(8)).unwrap();
+    let loaded = Grid::<Cell>::load_png(&path, 1).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.cells().len(), 6);
+    assert_eq!(loaded.get((0, 0)), Some(Cell::Wall));
+    assert_eq!(loaded.get((1, 1)), Some(Cell::Open));
+    assert_eq!(loaded.get((2, 1)), None);
+}
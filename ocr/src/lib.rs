@@ -0,0 +1,280 @@
+/*
+Recognizes the block letters Advent of Code uses to draw answers.
+
+Two fonts are known: the small one is 4 pixels wide and 6 pixels high, the
+large one 6 pixels wide and 10 pixels high. The font is chosen by the height of
+the lit pixels, letters are separated by at least one empty column.
+ */
+use std::fmt;
+
+const SMALL: [(char, &[&str]); 18] = [
+    ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', &[".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', &["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[rustfmt::skip]
+const LARGE: [(char, &[&str]); 15] = [
+    ('A', &["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', &["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', &[".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', &[".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', &["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', &["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', &["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', &["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', &["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', &["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', &["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', &["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', &["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+];
+
+// A letter which is not part of the font.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyph {
+    // Position of the letter in the text.
+    pub index: usize,
+    // Column of the letter's left edge in the cropped image.
+    pub column: usize,
+    // The pixels of the letter, '#' for lit and '.' for dark.
+    pub rows: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    // No pixel is lit.
+    Empty,
+    // The rows of the image have no pixels.
+    ZeroWidth,
+    // The lit pixels are neither 6 nor 10 rows high.
+    UnknownHeight(usize),
+    // Some letters were not recognized. `text` has a '?' in their place.
+    UnknownGlyphs { text: String, glyphs: Vec<Glyph> },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Empty => write!(f, "no pixels are lit"),
+            Error::ZeroWidth => write!(f, "the image has a width of zero"),
+            Error::UnknownHeight(h) => write!(f, "no font with a height of {} pixels", h),
+            Error::UnknownGlyphs { text, glyphs } => {
+                write!(f, "unknown letters in '{}':", text)?;
+                for g in glyphs {
+                    write!(f, "\nletter {} at column {}:", g.index, g.column)?;
+                    for r in &g.rows {
+                        write!(f, "\n{}", r)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/*
+Reads the text of an image given row by row, top row first.
+pixels - Lit state of each pixel.
+width  - Number of pixels per row.
+ */
+pub fn read_pixels(pixels: &[bool], width: usize) -> Result<String, Error> {
+    if width == 0 {
+        return Err(Error::ZeroWidth);
+    }
+    let points = pixels
+        .iter()
+        .enumerate()
+        .filter(|(_, lit)| **lit)
+        .map(|(i, _)| ((i % width) as i32, (i / width) as i32));
+    read_points(points)
+}
+
+// Reads the text drawn by a set of lit (x, y) coordinates, y growing downwards.
+pub fn read_points<I: IntoIterator<Item = (i32, i32)>>(points: I) -> Result<String, Error> {
+    let points = points.into_iter().collect::<Vec<_>>();
+    if points.is_empty() {
+        return Err(Error::Empty);
+    }
+    let min_x = points.iter().map(|p| p.0).min().unwrap();
+    let max_x = points.iter().map(|p| p.0).max().unwrap();
+    let min_y = points.iter().map(|p| p.1).min().unwrap();
+    let max_y = points.iter().map(|p| p.1).max().unwrap();
+    let width = (max_x - min_x + 1) as usize;
+    let height = (max_y - min_y + 1) as usize;
+
+    let font: &[(char, &[&str])] = match height {
+        6 => &SMALL,
+        10 => &LARGE,
+        h => return Err(Error::UnknownHeight(h)),
+    };
+
+    let mut image = vec![vec![false; width]; height];
+    for (x, y) in points {
+        image[(y - min_y) as usize][(x - min_x) as usize] = true;
+    }
+    let column_empty = |x: usize| image.iter().all(|row| !row[x]);
+
+    let mut text = String::new();
+    let mut unknown = vec![];
+    let mut x = 0;
+    while x < width {
+        if column_empty(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < width && !column_empty(x) {
+            x += 1;
+        }
+        let rows = image
+            .iter()
+            .map(|row| {
+                row[start..x]
+                    .iter()
+                    .map(|lit| if *lit { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        if let Some(c) = match_glyph(font, &rows) {
+            text.push(c);
+        } else {
+            unknown.push(Glyph {
+                index: text.chars().count(),
+                column: start,
+                rows,
+            });
+            text.push('?');
+        }
+    }
+
+    if unknown.is_empty() {
+        Ok(text)
+    } else {
+        Err(Error::UnknownGlyphs {
+            text,
+            glyphs: unknown,
+        })
+    }
+}
+
+// Compares the glyph with the font letters, ignoring their empty side columns.
+fn match_glyph(font: &[(char, &[&str])], rows: &[String]) -> Option<char> {
+    font.iter()
+        .find(|(_, letter)| {
+            let cols = (0..letter[0].len())
+                .filter(|x| letter.iter().any(|r| r.as_bytes()[*x] == b'#'))
+                .collect::<Vec<_>>();
+            cols.len() == rows[0].len()
+                && letter.iter().zip(rows).all(|(l, r)| {
+                    let l = l.as_bytes();
+                    cols.iter().zip(r.bytes()).all(|(x, px)| l[*x] == px)
+                })
+        })
+        .map(|(c, _)| *c)
+}
+
+#[cfg(test)]
+fn draw(rows: &[&str]) -> (Vec<bool>, usize) {
+    let pixels = rows
+        .iter()
+        .flat_map(|r| r.chars().map(|c| c == '#'))
+        .collect();
+    (pixels, rows[0].len())
+}
+
+#[test]
+fn test_small_font() {
+    let (pixels, width) = draw(&[
+        ".####..##..###..###...##..####...##.#..#.",
+        ".#....#..#.#..#.#..#.#..#.#.......#.#.#..",
+        ".###..#..#.#..#.###..#....###.....#.##...",
+        ".#....####.###..#..#.#....#.......#.#.#..",
+        ".#....#..#.#.#..#..#.#..#.#....#..#.#.#..",
+        ".#....#..#.#..#.###...##..#.....##..#..#.",
+        ".........................................",
+    ]);
+    assert_eq!(read_pixels(&pixels, width), Ok("FARBCFJK".to_string()));
+}
+
+#[test]
+fn test_large_font() {
+    let (pixels, width) = draw(&[
+        "#....#..######..#####.",
+        "#....#..#.......#....#",
+        "#....#..#.......#....#",
+        "#....#..#.......#....#",
+        "######..#####...#####.",
+        "#....#..#.......#..#..",
+        "#....#..#.......#...#.",
+        "#....#..#.......#...#.",
+        "#....#..#.......#....#",
+        "#....#..######..#....#",
+    ]);
+    assert_eq!(read_pixels(&pixels, width), Ok("HER".to_string()));
+}
+
+#[test]
+fn test_points() {
+    // "IL" with negative coordinates
+    let points = vec![
+        (-9, -3), (-8, -3), (-7, -3), (-8, -2), (-8, -1), (-8, 0), (-8, 1),
+        (-9, 2), (-8, 2), (-7, 2), (-5, -3), (-5, -2), (-5, -1), (-5, 0),
+        (-5, 1), (-5, 2), (-4, 2), (-3, 2), (-2, 2),
+    ];
+    assert_eq!(read_points(points), Ok("IL".to_string()));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(read_pixels(&[false; 12], 4), Err(Error::Empty));
+    assert_eq!(read_pixels(&[true; 12], 0), Err(Error::ZeroWidth));
+    assert_eq!(
+        read_points(vec![(0, 0), (0, 1), (0, 2)]),
+        Err(Error::UnknownHeight(3))
+    );
+
+    let (pixels, width) = draw(&[
+        "#..#.#.#",
+        "#..#.#.#",
+        "####.###",
+        "#..#...#",
+        "#..#...#",
+        "#..#...#",
+    ]);
+    let err = read_pixels(&pixels, width).unwrap_err();
+    assert_eq!(
+        err,
+        Error::UnknownGlyphs {
+            text: "H?".to_string(),
+            glyphs: vec![Glyph {
+                index: 1,
+                column: 5,
+                rows: vec!["#.#", "#.#", "###", "..#", "..#", "..#"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            }],
+        }
+    );
+    assert!(err.to_string().starts_with("unknown letters in 'H?':\nletter 1 at column 5:\n#.#"));
+}
@@ -0,0 +1,270 @@
+/*
+Named memory cells to patch Intcode programs before they run and to read
+results back after they halted. A patch set is parsed from lines like:
+
+    # comment
+    noun   @1 = 12
+    result @0
+
+Cells with a value are written into memory, cells without one are only read.
+ */
+use std::fmt;
+use std::str::FromStr;
+
+// Memory a patch set can be applied to.
+pub trait Memory<V> {
+    // The value at the address, None if the address is outside the memory.
+    fn peek(&self, addr: usize) -> Option<V>;
+    // Writes the value, returns false if the address is outside the memory.
+    fn poke(&mut self, addr: usize, value: V) -> bool;
+}
+
+impl<V: Copy> Memory<V> for [V] {
+    fn peek(&self, addr: usize) -> Option<V> {
+        self.get(addr).copied()
+    }
+
+    fn poke(&mut self, addr: usize, value: V) -> bool {
+        match self.get_mut(addr) {
+            Some(cell) => {
+                *cell = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<V: Copy> Memory<V> for Vec<V> {
+    fn peek(&self, addr: usize) -> Option<V> {
+        self.as_slice().peek(addr)
+    }
+
+    fn poke(&mut self, addr: usize, value: V) -> bool {
+        self.as_mut_slice().poke(addr, value)
+    }
+}
+
+// A cell whose address is outside the memory it is applied to or read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressError {
+    pub name: String,
+    pub addr: usize,
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "cell '{}': address {} is outside the memory",
+            self.name, self.addr
+        )
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell<V> {
+    pub name: String,
+    pub addr: usize,
+    pub value: Option<V>,
+}
+
+impl<V: Copy> Cell<V> {
+    pub fn read<M: Memory<V> + ?Sized>(&self, mem: &M) -> Result<V, AddressError> {
+        mem.peek(self.addr).ok_or_else(|| self.address_error())
+    }
+
+    fn address_error(&self) -> AddressError {
+        AddressError {
+            name: self.name.clone(),
+            addr: self.addr,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchSet<V> {
+    cells: Vec<Cell<V>>,
+}
+
+impl<V> Default for PatchSet<V> {
+    fn default() -> Self {
+        PatchSet { cells: vec![] }
+    }
+}
+
+impl<V: Copy> PatchSet<V> {
+    pub fn new() -> PatchSet<V> {
+        PatchSet::default()
+    }
+
+    // Adds a cell, replacing a cell with the same name.
+    pub fn insert(&mut self, cell: Cell<V>) {
+        if let Some(c) = self.cells.iter_mut().find(|c| c.name == cell.name) {
+            *c = cell;
+        } else {
+            self.cells.push(cell);
+        }
+    }
+
+    // Sets the value of a named cell. Returns false if there is no such cell.
+    pub fn set(&mut self, name: &str, value: V) -> bool {
+        if let Some(c) = self.cells.iter_mut().find(|c| c.name == name) {
+            c.value = Some(value);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Cell<V>> {
+        self.cells.iter().find(|c| c.name == name)
+    }
+
+    pub fn cells(&self) -> &[Cell<V>] {
+        &self.cells
+    }
+
+    // Writes all cells with a value into memory. Stops at the first cell outside of it.
+    pub fn apply<M: Memory<V> + ?Sized>(&self, mem: &mut M) -> Result<(), AddressError> {
+        for c in &self.cells {
+            if let Some(v) = c.value {
+                if !mem.poke(c.addr, v) {
+                    return Err(c.address_error());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Reads all cells back from memory.
+    pub fn read<M: Memory<V> + ?Sized>(&self, mem: &M) -> Result<Vec<(String, V)>, AddressError> {
+        self.cells
+            .iter()
+            .map(|c| Ok((c.name.clone(), c.read(mem)?)))
+            .collect()
+    }
+
+    // Reads a named cell from memory, None if there is no such cell.
+    pub fn read_one<M: Memory<V> + ?Sized>(
+        &self,
+        name: &str,
+        mem: &M,
+    ) -> Result<Option<V>, AddressError> {
+        self.get(name).map(|c| c.read(mem)).transpose()
+    }
+}
+
+impl<V> PatchSet<V>
+where
+    V: Copy + FromStr,
+    V::Err: fmt::Display,
+{
+    pub fn parse(s: &str) -> Result<PatchSet<V>, String> {
+        let mut set = PatchSet::new();
+        for (nr, line) in s.lines().enumerate() {
+            let line = match line.find('#') {
+                Some(ind) => &line[..ind],
+                None => line,
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let cell = PatchSet::parse_cell(line)
+                .map_err(|e| format!("line {}: {}: '{}'", nr + 1, e, line))?;
+            set.insert(cell);
+        }
+        Ok(set)
+    }
+
+    fn parse_cell(line: &str) -> Result<Cell<V>, String> {
+        let (cell, value) = match line.find('=') {
+            Some(ind) => (&line[..ind], Some(line[ind + 1..].trim())),
+            None => (line, None),
+        };
+        let mut parts = cell.split_whitespace();
+        let name = parts.next().ok_or("missing cell name")?;
+        let addr = parts.next().ok_or("missing cell address")?;
+        if parts.next().is_some() {
+            return Err("too many fields".to_string());
+        }
+        let addr = match addr.strip_prefix('@') {
+            Some(a) => a
+                .parse::<usize>()
+                .map_err(|e| format!("invalid address: {}", e))?,
+            None => return Err("address has to start with '@'".to_string()),
+        };
+        let value = match value {
+            Some(v) => Some(
+                v.parse::<V>()
+                    .map_err(|e| format!("invalid value: {}", e))?,
+            ),
+            None => None,
+        };
+        Ok(Cell {
+            name: name.to_string(),
+            addr,
+            value,
+        })
+    }
+}
+
+#[test]
+fn test_patch_set() {
+    let mut set = PatchSet::<i64>::parse(
+        "# multiply two cells\n\
+         lhs    @1 = 3\n\
+         rhs @2=-4 # inline comment\n\
+         \n\
+         result @0\n\
+         lhs @1 = 5\n",
+    )
+    .unwrap();
+    assert_eq!(set.cells().len(), 3);
+    assert_eq!(set.get("lhs").unwrap().value, Some(5));
+    assert_eq!(set.get("result").unwrap().value, None);
+
+    let mut mem = vec![0; 3];
+    set.apply(&mut mem).unwrap();
+    assert_eq!(mem, vec![0, 5, -4]);
+    mem[0] = 7;
+    assert_eq!(
+        set.read(&mem).unwrap(),
+        vec![
+            ("lhs".to_string(), 5),
+            ("rhs".to_string(), -4),
+            ("result".to_string(), 7)
+        ]
+    );
+    assert_eq!(set.read_one("result", &mem[..]), Ok(Some(7)));
+    assert_eq!(set.read_one("unknown", &mem), Ok(None));
+
+    assert!(set.set("rhs", 6));
+    assert!(!set.set("unknown", 6));
+
+    // addresses outside the memory are reported, not written
+    let mut short = vec![0; 2];
+    let err = AddressError {
+        name: "rhs".to_string(),
+        addr: 2,
+    };
+    assert_eq!(set.apply(&mut short), Err(err.clone()));
+    assert_eq!(short, vec![0, 5]);
+    assert_eq!(set.read(&short), Err(err.clone()));
+    assert_eq!(set.read_one("rhs", &short), Err(err.clone()));
+    assert_eq!(
+        err.to_string(),
+        "cell 'rhs': address 2 is outside the memory"
+    );
+
+    assert!(PatchSet::<i64>::parse("noun 1 = 12").is_err());
+    assert!(PatchSet::<i64>::parse("noun @1 = x").is_err());
+    assert!(PatchSet::<usize>::parse("noun @1 = -1").is_err());
+    assert_eq!(
+        PatchSet::<i64>::parse("ok @1\nnoun @x").unwrap_err(),
+        "line 2: invalid address: invalid digit found in string: 'noun @x'"
+    );
+}